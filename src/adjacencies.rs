@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use crate::graph::ProvinceGraph;
+use crate::grid::{Coords, Grid, GridVector, ProvinceKind};
+use crate::numastype::NumAsType;
+
+const HEADER: &str = "From;To;Type;Through;start_x;start_y;stop_x;stop_y;Comment\n";
+const FOOTER: &str = "-1;-1;-1;-1;-1;-1;-1;-1;-1\n";
+const DIRECTIONS:[(i64, i64); 8] = [(1, 0), (0, 1), (-1, 0), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Crossing{
+    pub from: u32,
+    pub to: u32,
+    pub through: u32,
+    pub start: Coords,
+    pub stop: Coords,
+    pub distance: f64
+}
+
+pub fn find_crossings(grids: &Vec<Grid>, graph: &ProvinceGraph, width: u32, height: u32, max_distance: u32) -> Vec<Crossing>{
+    if max_distance == 0{
+        return vec!()
+    }
+    let provinces = grids.provinces();
+    let raster = grids.province_raster(width, height);
+    let kind_at = |index: usize| raster[index].map(|province| provinces[province].kind);
+    let mut crossings: HashMap<(usize, usize), Crossing> = HashMap::new();
+    for index in 0..raster.len(){
+        let from = match raster[index]{
+            Some(from) if provinces[from].kind == ProvinceKind::Land => from,
+            _ => continue
+        };
+        let start = (index as u32).as_coords(width);
+        for &(dx, dy) in DIRECTIONS.iter(){
+            let step_length = ((dx * dx + dy * dy) as f64).sqrt();
+            let mut through = vec!();
+            for step in 1..=(max_distance as f64 / step_length) as i64 + 1{
+                let (x, y) = (start.x as i64 + dx * step, start.y as i64 + dy * step);
+                if x < 0 || y < 0 || x >= width as i64 || y >= height as i64{
+                    break
+                }
+                let current = (x + y * width as i64) as usize;
+                match kind_at(current){
+                    Some(ProvinceKind::Sea) => {
+                        through.push(raster[current].unwrap());
+                        continue
+                    },
+                    Some(ProvinceKind::Land) if !through.is_empty() => {
+                        let to = raster[current].unwrap();
+                        let pair = (from.min(to), from.max(to));
+                        if to == from || graph.are_adjacent(from as u32 + 1, to as u32 + 1){
+                            break
+                        }
+                        let distance = through.len() as f64 * step_length;
+                        let crossing = Crossing{
+                            from: pair.0 as u32 + 1,
+                            to: pair.1 as u32 + 1,
+                            through: through[through.len() / 2] as u32 + 1,
+                            start: if from < to {start} else {Coords::new(x as u32, y as u32)},
+                            stop: if from < to {Coords::new(x as u32, y as u32)} else {start},
+                            distance
+                        };
+                        let shorter = crossings.get(&pair).map_or(true, |existing| distance < existing.distance);
+                        if shorter{
+                            crossings.insert(pair, crossing);
+                        }
+                    },
+                    _ => {}
+                }
+                break
+            }
+        }
+    }
+    let mut crossings: Vec<Crossing> = crossings.into_iter().map(|(_, crossing)| crossing).collect();
+    crossings.sort_by_key(|crossing| (crossing.from, crossing.to));
+    crossings
+}
+
+// island links become straits only through a sea zone touching both ends, from the closest pair of their coast pixels on it
+pub fn island_crossings(grids: &Vec<Grid>, graph: &ProvinceGraph, width: u32, height: u32, links: &[(u32, u32)]) -> Vec<Crossing>{
+    let provinces = grids.provinces();
+    let raster = grids.province_raster(width, height);
+    let coast = |province: u32, sea: u32| -> Vec<Coords>{
+        provinces[province as usize - 1].province_pixels.iter().filter(|coords|
+            DIRECTIONS[..4].iter().any(|&(dx, dy)|{
+                let (x, y) = (coords.x as i64 + dx, coords.y as i64 + dy);
+                x >= 0 && y >= 0 && x < width as i64 && y < height as i64 && raster[(x + y * width as i64) as usize] == Some(sea as usize - 1)
+            })
+        ).copied().collect()
+    };
+    let mut crossings = vec!();
+    for &(from, to) in links.iter(){
+        let seas = graph.neighbours(from).into_iter().filter(|&sea| graph.node(sea).kind == ProvinceKind::Sea && graph.are_adjacent(sea, to));
+        let mut best: Option<Crossing> = None;
+        for sea in seas{
+            let (starts, stops) = (coast(from, sea), coast(to, sea));
+            for start in starts.iter(){
+                for stop in stops.iter(){
+                    let distance = ((start.x as f64 - stop.x as f64).powi(2) + (start.y as f64 - stop.y as f64).powi(2)).sqrt();
+                    if best.map_or(true, |best| distance < best.distance){
+                        best = Some(Crossing{from, to, through: sea, start: *start, stop: *stop, distance});
+                    }
+                }
+            }
+        }
+        crossings.extend(best);
+    }
+    crossings
+}
+
+// adjacency coordinates are measured from the bottom left corner of the map
+pub fn adjacencies_csv(crossings: &[Crossing], height: u32) -> String{
+    let mut csv = String::from(HEADER);
+    for crossing in crossings.iter(){
+        csv.push_str(&format!(
+            "{};{};sea;{};{};{};{};{};strait_{}_{}\n",
+            crossing.from, crossing.to, crossing.through,
+            crossing.start.x, height - 1 - crossing.start.y, crossing.stop.x, height - 1 - crossing.stop.y,
+            crossing.from, crossing.to
+        ));
+    }
+    csv.push_str(FOOTER);
+    csv
+}
@@ -0,0 +1,309 @@
+use clap::{Args, Parser, Subcommand};
+use ck3_random_map::output::OutputMode;
+use ck3_random_map::error::Result;
+use ck3_random_map::growth::{Growth, GROWTHS};
+use ck3_random_map::localization::EXTRA_LANGUAGES;
+use ck3_random_map::seeding::{Seeding, SEEDINGS};
+use ck3_random_map::settings::{unknown_preset, NoiseSettings, Settings, PRESETS};
+
+#[derive(Parser)]
+#[command(name = "ck3_random_map", about = "Generates random Crusader Kings III maps")]
+pub struct Cli{
+    #[command(subcommand)]
+    pub command: Command
+}
+
+#[derive(Subcommand)]
+pub enum Command{
+    /// Generate a complete mod folder
+    Generate{
+        #[command(flatten)]
+        map: MapArgs,
+        /// Overwrite files in a root folder that is not empty
+        #[arg(long)]
+        force: bool,
+        /// Remove the generated folders of a previous run before writing
+        #[arg(long)]
+        clean: bool
+    },
+    /// Render the terrain map to a single image without writing a mod
+    Preview{
+        #[command(flatten)]
+        map: MapArgs,
+        /// Path of the preview image
+        #[arg(long, short, default_value = "preview.png")]
+        output: String
+    },
+    /// Check a previously generated mod folder for missing or inconsistent files
+    Validate{
+        /// Mod folder to check
+        #[arg(long, default_value = "mod")]
+        root_folder: String
+    },
+    /// Print land, terrain and province statistics without writing any files
+    Stats(MapArgs)
+}
+
+#[derive(Args)]
+pub struct MapArgs{
+    /// TOML or JSON profile describing the world, flags override its values
+    #[arg(long)]
+    pub config: Option<String>,
+    /// Built-in profile to start from, replaces the preset named in the profile
+    #[arg(long, value_parser = PRESETS)]
+    pub preset: Option<String>,
+    /// Seed used for every noise map [default: 9787]
+    #[arg(long)]
+    pub seed: Option<u32>,
+    /// Map width in pixels [default: 1024]
+    #[arg(long)]
+    pub width: Option<u32>,
+    /// Map height in pixels [default: 512]
+    #[arg(long)]
+    pub height: Option<u32>,
+    /// Side length of the grid cells provinces are seeded in [default: 64]
+    #[arg(long)]
+    pub province_grid_size: Option<u32>,
+    /// Side length of the grid cells sea zones are seeded in [default: 128]
+    #[arg(long)]
+    pub sea_zone_grid_size: Option<u32>,
+    /// Provinces with fewer pixels are merged into their longest-bordering neighbour [default: 100]
+    #[arg(long)]
+    pub min_province_size: Option<u32>,
+    /// Largest distance in pixels province borders are pushed around by noise, 0 keeps them straight [default: 0]
+    #[arg(long)]
+    pub border_jitter: Option<f64>,
+    /// Titular kingdoms without de jure land created in every empire [default: 0]
+    #[arg(long)]
+    pub titular_titles: Option<u32>,
+    /// Languages that get a copy of the english localization, separated by commas [default: none]
+    #[arg(long, value_parser = EXTRA_LANGUAGES, value_delimiter = ',')]
+    pub extra_languages: Vec<String>,
+    /// How province and sea zone seeds are placed [default: grid]
+    #[arg(long, value_parser = SEEDINGS)]
+    pub seeding: Option<String>,
+    /// Relaxation passes applied to the seeds when seeding is lloyd [default: 3]
+    #[arg(long)]
+    pub lloyd_iterations: Option<u32>,
+    /// How strongly habitability shrinks provinces in fertile land and grows them in deserts, taiga and mountains [default: 0]
+    #[arg(long)]
+    pub terrain_sizing: Option<f64>,
+    /// How provinces grow from their seeds, cost follows ridges, rivers and terrain borders [default: distance]
+    #[arg(long, value_parser = GROWTHS)]
+    pub growth: Option<String>,
+    /// Roughly how many land provinces to seed, overrides province_grid_size
+    #[arg(long)]
+    pub target_land_provinces: Option<u32>,
+    /// Roughly how many sea zones to seed, overrides sea_zone_grid_size
+    #[arg(long)]
+    pub target_sea_zones: Option<u32>,
+    /// Share of mountain pixels above which a province becomes impassable [default: 0.75]
+    #[arg(long)]
+    pub impassable_mountain_share: Option<f64>,
+    /// Enclosed water bodies smaller than this many pixels are filled in as land [default: 16]
+    #[arg(long)]
+    pub min_lake_size: Option<u32>,
+    /// Enclosed water bodies up to this many pixels become lakes instead of sea zones [default: 4000]
+    #[arg(long)]
+    pub max_lake_size: Option<u32>,
+    /// Flow a land pixel needs to collect before it is drawn as a river [default: 400]
+    #[arg(long)]
+    pub river_threshold: Option<f64>,
+    /// Flow above which a river becomes a navigable river province [default: 12000]
+    #[arg(long)]
+    pub river_province_threshold: Option<f64>,
+    /// Width in pixels of river provinces, 0 disables them [default: 3]
+    #[arg(long)]
+    pub river_province_width: Option<u32>,
+    /// Widest water gap in pixels that armies can cross between land provinces, 0 disables crossings [default: 12]
+    #[arg(long)]
+    pub max_crossing_distance: Option<u32>,
+    /// Heightmap value above which a pixel counts as land [default: 4352]
+    #[arg(long)]
+    pub land_color: Option<u16>,
+    /// Folder the mod is written to [default: mod]
+    #[arg(long)]
+    pub root_folder: Option<String>,
+    /// Mod name shown in the launcher [default: Random Map]
+    #[arg(long)]
+    pub mod_name: Option<String>,
+    /// Version of the generated mod [default: 0.1.0]
+    #[arg(long)]
+    pub mod_version: Option<String>,
+    /// Game version the mod declares support for [default: 1.*]
+    #[arg(long)]
+    pub supported_version: Option<String>,
+    /// Sea level of the height noise, between -1 and 1 [default: -0.2]
+    #[arg(long, allow_hyphen_values = true)]
+    pub sea_level: Option<f64>,
+    #[command(flatten)]
+    pub height_noise: HeightNoiseArgs,
+    #[command(flatten)]
+    pub moisture_noise: MoistureNoiseArgs,
+    #[command(flatten)]
+    pub temperature_noise: TemperatureNoiseArgs
+}
+impl MapArgs{
+    pub fn apply(&self, settings: &mut Settings){
+        if let Some(seed) = self.seed{
+            settings.seed = seed;
+        }
+        if let Some(width) = self.width{
+            settings.width = width;
+        }
+        if let Some(height) = self.height{
+            settings.height = height;
+        }
+        if let Some(province_grid_size) = self.province_grid_size{
+            settings.province_grid_size = province_grid_size;
+        }
+        if let Some(sea_zone_grid_size) = self.sea_zone_grid_size{
+            settings.sea_zone_grid_size = sea_zone_grid_size;
+        }
+        if let Some(target_land_provinces) = self.target_land_provinces{
+            settings.target_land_provinces = Some(target_land_provinces);
+        }
+        if let Some(target_sea_zones) = self.target_sea_zones{
+            settings.target_sea_zones = Some(target_sea_zones);
+        }
+        if !self.extra_languages.is_empty(){
+            settings.extra_languages = self.extra_languages.clone();
+        }
+        if let Some(seeding) = self.seeding.as_deref().and_then(Seeding::from_name){
+            settings.seeding = seeding;
+        }
+        if let Some(lloyd_iterations) = self.lloyd_iterations{
+            settings.lloyd_iterations = lloyd_iterations;
+        }
+        if let Some(terrain_sizing) = self.terrain_sizing{
+            settings.terrain_sizing = terrain_sizing;
+        }
+        if let Some(growth) = self.growth.as_deref().and_then(Growth::from_name){
+            settings.growth = growth;
+        }
+        if let Some(min_province_size) = self.min_province_size{
+            settings.min_province_size = min_province_size;
+        }
+        if let Some(border_jitter) = self.border_jitter{
+            settings.border_jitter = border_jitter;
+        }
+        if let Some(titular_titles) = self.titular_titles{
+            settings.titles.titular_titles = titular_titles;
+        }
+        if let Some(impassable_mountain_share) = self.impassable_mountain_share{
+            settings.impassable_mountain_share = impassable_mountain_share;
+        }
+        if let Some(min_lake_size) = self.min_lake_size{
+            settings.min_lake_size = min_lake_size;
+        }
+        if let Some(max_lake_size) = self.max_lake_size{
+            settings.max_lake_size = max_lake_size;
+        }
+        if let Some(river_threshold) = self.river_threshold{
+            settings.river_threshold = river_threshold;
+        }
+        if let Some(river_province_threshold) = self.river_province_threshold{
+            settings.river_province_threshold = river_province_threshold;
+        }
+        if let Some(river_province_width) = self.river_province_width{
+            settings.river_province_width = river_province_width;
+        }
+        if let Some(max_crossing_distance) = self.max_crossing_distance{
+            settings.max_crossing_distance = max_crossing_distance;
+        }
+        if let Some(land_color) = self.land_color{
+            settings.land_color = land_color;
+        }
+        if let Some(root_folder) = &self.root_folder{
+            settings.root_folder = root_folder.clone();
+        }
+        if let Some(mod_name) = &self.mod_name{
+            settings.descriptor.name = mod_name.clone();
+        }
+        if let Some(mod_version) = &self.mod_version{
+            settings.descriptor.version = mod_version.clone();
+        }
+        if let Some(supported_version) = &self.supported_version{
+            settings.descriptor.supported_version = supported_version.clone();
+        }
+        if let Some(sea_level) = self.sea_level{
+            settings.height_noise.sea_level = sea_level;
+        }
+        apply_noise(&mut settings.height_noise, self.height_noise.height_octaves, self.height_noise.height_lacunarity, self.height_noise.height_scale);
+        apply_noise(&mut settings.moisture_noise, self.moisture_noise.moisture_octaves, self.moisture_noise.moisture_lacunarity, self.moisture_noise.moisture_scale);
+        apply_noise(&mut settings.temperature_noise, self.temperature_noise.temperature_octaves, self.temperature_noise.temperature_lacunarity, self.temperature_noise.temperature_scale);
+    }
+    pub fn to_settings(&self) -> Result<Settings>{
+        let mut settings = match (&self.config, &self.preset){
+            (Some(config), preset) => Settings::load(config, preset.as_deref())?,
+            (None, Some(preset)) => Settings::preset(preset).ok_or_else(|| unknown_preset(preset))?,
+            (None, None) => Settings::default()
+        };
+        self.apply(&mut settings);
+        settings.validate()?;
+        Ok(settings)
+    }
+}
+
+fn apply_noise(noise: &mut NoiseSettings, octaves: Option<usize>, lacunarity: Option<f64>, scale: Option<f64>){
+    if let Some(octaves) = octaves{
+        noise.octaves = octaves;
+    }
+    if let Some(lacunarity) = lacunarity{
+        noise.lacunarity = lacunarity;
+    }
+    if let Some(scale) = scale{
+        noise.scale = scale;
+    }
+}
+
+#[derive(Args)]
+pub struct HeightNoiseArgs{
+    /// Octaves of the height noise [default: 7]
+    #[arg(long)]
+    pub height_octaves: Option<usize>,
+    /// Lacunarity of the height noise [default: 2.12323]
+    #[arg(long)]
+    pub height_lacunarity: Option<f64>,
+    /// Scale of the height noise [default: 3.0]
+    #[arg(long)]
+    pub height_scale: Option<f64>
+}
+
+#[derive(Args)]
+pub struct MoistureNoiseArgs{
+    /// Octaves of the moisture noise [default: 3]
+    #[arg(long)]
+    pub moisture_octaves: Option<usize>,
+    /// Lacunarity of the moisture noise [default: 2.02345]
+    #[arg(long)]
+    pub moisture_lacunarity: Option<f64>,
+    /// Scale of the moisture noise [default: 3.0]
+    #[arg(long)]
+    pub moisture_scale: Option<f64>
+}
+
+#[derive(Args)]
+pub struct TemperatureNoiseArgs{
+    /// Octaves of the temperature noise [default: 3]
+    #[arg(long)]
+    pub temperature_octaves: Option<usize>,
+    /// Lacunarity of the temperature noise [default: 2.201348]
+    #[arg(long)]
+    pub temperature_lacunarity: Option<f64>,
+    /// Scale of the temperature noise [default: 3.0]
+    #[arg(long)]
+    pub temperature_scale: Option<f64>
+}
+
+pub fn output_mode(force: bool, clean: bool) -> OutputMode{
+    if clean{
+        OutputMode::Clean
+    }
+    else if force{
+        OutputMode::Force
+    }
+    else{
+        OutputMode::Protect
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::VecDeque;
+use crate::grid::{Grid, GridVector, ProvinceKind};
+use crate::water::neighbour_indices;
+
+fn compatible(a: ProvinceKind, b: ProvinceKind) -> bool{
+    match (a, b){
+        (ProvinceKind::Lake, _) | (_, ProvinceKind::Lake) => false,
+        (ProvinceKind::Land, ProvinceKind::Impassable) | (ProvinceKind::Impassable, ProvinceKind::Land) => true,
+        (a, b) => a == b
+    }
+}
+
+fn components(pixels: &[usize], owner: &Vec<Option<usize>>, grid: usize, width: u32, height: u32) -> Vec<Vec<usize>>{
+    let mut visited = vec![false; owner.len()];
+    let mut components = vec!();
+    for &start in pixels.iter(){
+        if visited[start]{
+            continue
+        }
+        let mut component = vec!();
+        let mut queue = VecDeque::new();
+        visited[start] = true;
+        queue.push_back(start);
+        while let Some(index) = queue.pop_front(){
+            component.push(index);
+            for neighbour in neighbour_indices(index, width, height){
+                if owner[neighbour] == Some(grid) && !visited[neighbour]{
+                    visited[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+fn border_lengths(pixels: &[usize], grid: usize, grids: &Vec<Grid>, owner: &Vec<Option<usize>>, width: u32, height: u32) -> Vec<usize>{
+    let mut borders = vec![0; grids.len()];
+    for &index in pixels.iter(){
+        for neighbour in neighbour_indices(index, width, height){
+            match owner[neighbour]{
+                Some(other) if other != grid => borders[other] += 1,
+                _ => {}
+            }
+        }
+    }
+    borders
+}
+
+// the province sharing the longest border with the given pixels, ties go to the lower index
+fn longest_border<F: Fn(usize) -> bool>(borders: &[usize], accept: F) -> Option<usize>{
+    (0..borders.len()).filter(|&other| borders[other] > 0 && accept(other)).max_by(|a, b| borders[*a].cmp(&borders[*b]).then(b.cmp(a)))
+}
+
+// new provinces take the next unused colors, which follow the colors of all existing grids
+pub fn make_contiguous(grids: &mut Vec<Grid>, width: u32, height: u32, min_province_size: u32, colors: &[u32]){
+    let mut owner = grids.owners(width, height);
+    let mut sizes: Vec<usize> = grids.iter().map(|grid| grid.province_pixels.len()).collect();
+    for i in 0..grids.len(){
+        if grids[i].kind == ProvinceKind::Lake{
+            continue
+        }
+        let pixels: Vec<usize> = grids[i].province_pixels.iter().map(|coords| coords.as_index(width) as usize).collect();
+        let mut fragments = components(&pixels, &owner, i, width, height);
+        if fragments.len() < 2{
+            continue
+        }
+        let largest = (0..fragments.len()).max_by(|a, b| fragments[*a].len().cmp(&fragments[*b].len()).then(b.cmp(a))).unwrap();
+        fragments.remove(largest);
+        for fragment in fragments{
+            // fragments cut off by water become provinces of their own when large enough, otherwise they sink into the water around them
+            let borders = border_lengths(&fragment, i, grids, &owner, width, height);
+            let target = match longest_border(&borders, |other| compatible(grids[i].kind, grids[other].kind)){
+                Some(target) => Some(target),
+                None if fragment.len() >= min_province_size as usize => {
+                    let index = grids.len() as u32;
+                    grids.push(Grid::new(vec!(), vec!(), index, colors, grids[i].kind));
+                    sizes.push(0);
+                    Some(grids.len() - 1)
+                },
+                None => longest_border(&borders, |_| true)
+            };
+            if let Some(target) = target{
+                for &index in fragment.iter(){
+                    owner[index] = Some(target);
+                }
+                sizes[i] -= fragment.len();
+                sizes[target] += fragment.len();
+            }
+        }
+    }
+    let mut small: Vec<usize> = (0..grids.len()).filter(|&i| sizes[i] > 0 && sizes[i] < min_province_size as usize && grids[i].kind != ProvinceKind::Lake).collect();
+    small.sort_by_key(|&i| (sizes[i], i));
+    for i in small{
+        if sizes[i] == 0 || sizes[i] >= min_province_size as usize{
+            continue
+        }
+        let pixels: Vec<usize> = (0..owner.len()).filter(|&index| owner[index] == Some(i)).collect();
+        let borders = border_lengths(&pixels, i, grids, &owner, width, height);
+        let target = longest_border(&borders, |other| compatible(grids[i].kind, grids[other].kind)).or_else(|| longest_border(&borders, |_| true));
+        if let Some(target) = target{
+            for &index in pixels.iter(){
+                owner[index] = Some(target);
+            }
+            sizes[target] += sizes[i];
+            sizes[i] = 0;
+        }
+    }
+    grids.assign_owners(&owner, width);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::grid::Coords;
+
+    const WIDTH: u32 = 12;
+    const HEIGHT: u32 = 6;
+
+    fn grid(index: u32, kind: ProvinceKind, pixels: Vec<Coords>) -> Grid{
+        let mut grid = Grid::new(vec!(), vec!(), index, &[1, 2, 3, 4, 5, 6], kind);
+        grid.province_pixels = pixels;
+        grid
+    }
+
+    #[test]
+    fn islets_and_undersized_provinces_leave_single_components(){
+        // land on the left half with a one pixel islet out at sea, a two pixel island and a sea zone around them
+        let mut land: Vec<Coords> = (0..HEIGHT).flat_map(|y| (0..5).map(move |x| Coords::new(x, y))).collect();
+        land.push(Coords::new(10, 3));
+        let island = vec!(Coords::new(8, 1), Coords::new(8, 2));
+        let taken: Vec<Coords> = land.iter().chain(island.iter()).copied().collect();
+        let sea: Vec<Coords> = (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| Coords::new(x, y))).filter(|coords| !taken.contains(coords)).collect();
+        let mut grids = vec!(grid(0, ProvinceKind::Land, land), grid(1, ProvinceKind::Land, island), grid(2, ProvinceKind::Sea, sea));
+        let min_province_size = 4;
+        make_contiguous(&mut grids, WIDTH, HEIGHT, min_province_size, &[1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(grids.iter().map(|grid| grid.province_pixels.len()).sum::<usize>(), (WIDTH*HEIGHT) as usize);
+        let owner = grids.owners(WIDTH, HEIGHT);
+        for (i, grid) in grids.iter().enumerate().filter(|(_, grid)| !grid.province_pixels.is_empty()){
+            let pixels: Vec<usize> = grid.province_pixels.iter().map(|coords| coords.as_index(WIDTH) as usize).collect();
+            assert_eq!(components(&pixels, &owner, i, WIDTH, HEIGHT).len(), 1, "province {} is split", i);
+            assert!(pixels.len() >= min_province_size as usize, "province {} has {} pixels", i, pixels.len());
+        }
+        assert_eq!(grids[0].province_pixels.len(), 30);
+        assert!(grids[1].province_pixels.is_empty());
+    }
+}
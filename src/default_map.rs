@@ -0,0 +1,71 @@
+use crate::grid::{Grid, GridVector, ProvinceKind};
+
+const PROVINCE_LISTS:[(&str, ProvinceKind); 4] = [
+    ("sea_zones", ProvinceKind::Sea),
+    ("lakes", ProvinceKind::Lake),
+    ("impassable_mountains", ProvinceKind::Impassable),
+    ("river_provinces", ProvinceKind::River)
+];
+const MIN_RANGE_LENGTH: usize = 3;
+
+pub fn default_map(grids: &Vec<Grid>) -> String{
+    let mut default_map = String::from("definitions = \"definition.csv\"\n");
+    default_map.push_str("provinces = \"provinces.png\"\n");
+    default_map.push_str("heightmap = \"heightmap.png\"\n");
+    default_map.push_str("rivers = \"rivers.png\"\n");
+    default_map.push_str("topology = \"heightmap.heightmap\"\n");
+    default_map.push_str("adjacencies = \"adjacencies.csv\"\n\n");
+    for (key, kind) in PROVINCE_LISTS.iter(){
+        default_map.push_str(&province_list(key, &grids.province_ids(*kind)));
+    }
+    default_map
+}
+
+fn province_list(key: &str, ids: &[u32]) -> String{
+    let mut result = String::new();
+    let mut singles = vec!();
+    let mut start = 0;
+    while start < ids.len(){
+        let mut end = start;
+        while end + 1 < ids.len() && ids[end + 1] == ids[end] + 1{
+            end += 1;
+        }
+        if end - start + 1 >= MIN_RANGE_LENGTH{
+            result.push_str(&format!("{} = RANGE {{ {} {} }}\n", key, ids[start], ids[end]));
+        }
+        else{
+            singles.extend_from_slice(&ids[start..=end]);
+        }
+        start = end + 1;
+    }
+    if !singles.is_empty(){
+        let list: Vec<String> = singles.iter().map(|id| id.to_string()).collect();
+        result.push_str(&format!("{} = LIST {{ {} }}\n", key, list.join(" ")));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn runs_of_three_become_ranges(){
+        assert_eq!(province_list("sea_zones", &[4, 5, 6, 7]), "sea_zones = RANGE { 4 7 }\n");
+    }
+
+    #[test]
+    fn short_runs_become_a_list(){
+        assert_eq!(province_list("lakes", &[2, 3, 9]), "lakes = LIST { 2 3 9 }\n");
+    }
+
+    #[test]
+    fn ranges_and_singles_mix(){
+        assert_eq!(province_list("sea_zones", &[1, 3, 4, 5, 8]), "sea_zones = RANGE { 3 5 }\nsea_zones = LIST { 1 8 }\n");
+    }
+
+    #[test]
+    fn empty_lists_write_nothing(){
+        assert_eq!(province_list("river_provinces", &[]), "");
+    }
+}
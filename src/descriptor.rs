@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use crate::terrain::Terrain;
+
+const REPLACE_PATHS:[&str; 5] = ["map_data", "common/landed_titles", "common/province_terrain", "history/provinces", "history/titles"];
+const THUMBNAIL_SIZE: u32 = 256;
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DescriptorSettings{
+    pub name: String,
+    pub version: String,
+    pub supported_version: String
+}
+impl Default for DescriptorSettings{
+    fn default() -> Self{
+        DescriptorSettings{
+            name: String::from("Random Map"),
+            version: String::from("0.1.0"),
+            supported_version: String::from("1.*")
+        }
+    }
+}
+impl DescriptorSettings{
+    pub fn to_descriptor(&self, path: Option<&str>) -> String{
+        let mut descriptor = format!("version=\"{}\"\n", self.version);
+        descriptor.push_str("tags={\n\t\"Total Conversion\"\n\t\"Map\"\n}\n");
+        descriptor.push_str(&format!("name=\"{}\"\n", self.name));
+        descriptor.push_str(&format!("supported_version=\"{}\"\n", self.supported_version));
+        descriptor.push_str("picture=\"thumbnail.png\"\n");
+        for replace_path in REPLACE_PATHS.iter(){
+            descriptor.push_str(&format!("replace_path=\"{}\"\n", replace_path));
+        }
+        if let Some(path) = path{
+            descriptor.push_str(&format!("path=\"{}\"\n", path));
+        }
+        descriptor
+    }
+}
+
+pub fn thumbnail(terrain_map: &im::RgbImage) -> im::RgbImage{
+    let (width, height) = terrain_map.dimensions();
+    let scale = THUMBNAIL_SIZE as f64 / width.max(height) as f64;
+    let (scaled_width, scaled_height) = (((width as f64 * scale).round() as u32).max(1), ((height as f64 * scale).round() as u32).max(1));
+    let scaled = im::imageops::resize(terrain_map, scaled_width, scaled_height, im::imageops::FilterType::Triangle);
+    let mut thumbnail = im::ImageBuffer::from_pixel(THUMBNAIL_SIZE, THUMBNAIL_SIZE, Terrain::Ocean.color());
+    im::imageops::overlay(&mut thumbnail, &scaled, (THUMBNAIL_SIZE - scaled_width) / 2, (THUMBNAIL_SIZE - scaled_height) / 2);
+    thumbnail
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum Error{
+    Io{path: PathBuf, source: io::Error},
+    Image{path: PathBuf, source: im::ImageError},
+    Png{path: PathBuf, source: png::EncodingError},
+    Config(String),
+    OutputExists(PathBuf),
+    Generation{stage: &'static str, message: String}
+}
+impl Error{
+    pub fn io(path: &Path, source: io::Error) -> Self{
+        Error::Io{path: path.to_path_buf(), source}
+    }
+    pub fn image(path: &Path, source: im::ImageError) -> Self{
+        Error::Image{path: path.to_path_buf(), source}
+    }
+    pub fn png(path: &Path, source: png::EncodingError) -> Self{
+        Error::Png{path: path.to_path_buf(), source}
+    }
+    pub fn generation(stage: &'static str, message: &str) -> Self{
+        Error::Generation{stage, message: message.to_string()}
+    }
+}
+impl fmt::Display for Error{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self{
+            Error::Io{path, source} => write!(f, "{}: {}", path.display(), source),
+            Error::Image{path, source} => write!(f, "{}: {}", path.display(), source),
+            Error::Png{path, source} => write!(f, "{}: {}", path.display(), source),
+            Error::Config(message) => write!(f, "invalid configuration: {}", message),
+            Error::OutputExists(path) => write!(f, "{} already contains files, pass --force to overwrite them or --clean to replace them", path.display()),
+            Error::Generation{stage, message} => write!(f, "{} stage failed: {}", stage, message)
+        }
+    }
+}
+impl std::error::Error for Error{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)>{
+        match self{
+            Error::Io{source, ..} => Some(source),
+            Error::Image{source, ..} => Some(source),
+            Error::Png{source, ..} => Some(source),
+            _ => None
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
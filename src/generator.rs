@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use rand::seq::SliceRandom;
+use crate::adjacencies::{self, Crossing};
+use crate::descriptor;
+use crate::{NUM_OF_COLORS, LAND_FLAT_COLOR, WATER_FLAT_COLOR};
+use crate::error::{Error, Result};
+use crate::growth::{self, Growth};
+use crate::jitter;
+use crate::landmass::{self, Landmasses};
+use crate::localization;
+use crate::names::NameGenerator;
+use crate::graph::ProvinceGraph;
+use crate::contiguity;
+use crate::default_map;
+use crate::grid::{Coords, Grid, GridVector, Layout, ProvinceKind};
+use crate::noise;
+use crate::numastype::NumAsType;
+use crate::output::OutputRoot;
+use crate::random::{self, Stage};
+use crate::river::{self, FlowMap};
+use crate::seeding::{self, Seeding};
+use crate::settings::Settings;
+use crate::terrain::{self, Terrain, TerrainVector};
+use crate::titles::TitleTree;
+use crate::water::{self, WaterBody};
+
+pub type NoiseMap = im::ImageBuffer<im::Luma<u16>, Vec<u16>>;
+
+const MIN_GRID_SIZE: u32 = 4;
+
+pub struct Heightmap{
+    pub height_map: NoiseMap,
+    pub map_pixels: Vec<bool>,
+    pub lakes: Vec<WaterBody>
+}
+impl Heightmap{
+    pub fn width(&self) -> u32{
+        self.height_map.width()
+    }
+    pub fn height(&self) -> u32{
+        self.height_map.height()
+    }
+    pub fn land_count(&self) -> usize{
+        self.map_pixels.iter().filter(|&&is_land| is_land).count()
+    }
+}
+
+pub struct Climate{
+    pub moisture_map: NoiseMap,
+    pub temperature_map: NoiseMap
+}
+
+pub struct TerrainLayer{
+    pub terrain_map: Vec<Terrain>,
+    pub masks: Vec<im::GrayImage>
+}
+
+pub struct Rivers{
+    pub flow: FlowMap,
+    pub pixels: Vec<u8>,
+    pub major: Vec<bool>
+}
+impl Rivers{
+    pub fn count(&self) -> usize{
+        self.pixels.iter().filter(|&&pixel| pixel < river::WATER).count()
+    }
+}
+
+pub struct Provinces{
+    pub grids: Vec<Grid>,
+    pub land_grid_size: u32,
+    pub sea_grid_size: u32,
+    pub graph: ProvinceGraph,
+    pub crossings: Vec<Crossing>
+}
+impl Provinces{
+    pub fn count(&self) -> usize{
+        self.grids.iter().filter(|grid| grid.province_pixels.len() > 0).count()
+    }
+    pub fn count_kind(&self, kind: ProvinceKind) -> usize{
+        self.grids.province_ids(kind).len()
+    }
+}
+
+pub struct Titles{
+    pub landmasses: Landmasses,
+    pub tree: TitleTree,
+    pub island_crossings: Vec<Crossing>,
+    pub province_names: Vec<String>
+}
+
+pub struct GeneratedMap{
+    pub heightmap: Heightmap,
+    pub climate: Climate,
+    pub terrain: TerrainLayer,
+    pub rivers: Rivers,
+    pub provinces: Provinces,
+    pub titles: Titles
+}
+
+pub struct MapGenerator{
+    pub settings: Settings
+}
+impl MapGenerator{
+    pub fn new(settings: Settings) -> Self{
+        MapGenerator{settings}
+    }
+    pub fn heightmap(&self) -> Result<Heightmap>{
+        let settings = &self.settings;
+        let mut height_map = noise::generate_noise_map(settings.width, settings.height, &settings.height_noise, settings.seed);
+        let mut map_pixels: Vec<bool> = height_map.pixels().map(|pixel| pixel[0] > settings.land_color).collect();
+        let mut lakes = vec!();
+        for body in water::collect_water_bodies(&map_pixels, settings.width, settings.height){
+            if !body.is_lake(settings.max_lake_size){
+                continue
+            }
+            if body.pixels.len() >= settings.min_lake_size as usize{
+                lakes.push(body);
+                continue
+            }
+            for coords in body.pixels.iter(){
+                map_pixels[coords.as_index(settings.width) as usize] = true;
+                height_map.put_pixel(coords.x, coords.y, im::Luma([settings.land_color.saturating_add(1)]));
+            }
+        }
+        let heightmap = Heightmap{height_map, map_pixels, lakes};
+        if heightmap.land_count() == 0{
+            return Err(Error::generation("heightmap", "no pixels are above land_color"))
+        }
+        Ok(heightmap)
+    }
+    pub fn climate(&self, heightmap: &Heightmap) -> Climate{
+        let settings = &self.settings;
+        let (width, height) = (heightmap.width(), heightmap.height());
+        Climate{
+            moisture_map: noise::generate_noise_map(width, height, &settings.moisture_noise, settings.seed.wrapping_add(10)),
+            temperature_map: noise::generate_noise_map(width, height, &settings.temperature_noise, settings.seed.wrapping_add(15))
+        }
+    }
+    pub fn terrain(&self, heightmap: &Heightmap, climate: &Climate) -> TerrainLayer{
+        let (terrain_map, masks) = Vec::<Terrain>::collect_terrain(&heightmap.height_map, &climate.moisture_map, &climate.temperature_map, &self.settings);
+        TerrainLayer{terrain_map, masks}
+    }
+    pub fn rivers(&self, heightmap: &Heightmap, climate: &Climate) -> Rivers{
+        let flow = river::compute_flow(&heightmap.height_map, &heightmap.map_pixels, &climate.moisture_map);
+        let pixels = river::trace_rivers(&flow, &heightmap.map_pixels, heightmap.width(), heightmap.height(), self.settings.river_threshold);
+        let major = river::major_rivers(&flow, &pixels, heightmap.width(), heightmap.height(), self.settings.river_province_threshold, self.settings.river_province_width);
+        Rivers{flow, pixels, major}
+    }
+    pub fn provinces(&self, heightmap: &Heightmap, terrain: &TerrainLayer, rivers: &Rivers) -> Result<Provinces>{
+        let settings = &self.settings;
+        let mut colors: Vec<u32> = (0..NUM_OF_COLORS).collect();
+        colors.shuffle(&mut random::stage_rng(settings.seed, Stage::Colors));
+        let land_pixels: Vec<bool> = heightmap.map_pixels.iter().zip(rivers.major.iter()).map(|(&is_land, &is_river)| is_land && !is_river).collect();
+        let scales = if settings.terrain_sizing > 0.0 {Some(seeding::habitability_scales(&terrain.terrain_map, &land_pixels, settings.terrain_sizing))} else {None};
+        let land_grid_size = settings.target_land_provinces.map_or(settings.province_grid_size, |target| self.fit_grid_size(heightmap, &land_pixels, scales.as_ref(), &colors, target, Stage::Grids));
+        let land_layout = Layout{width: heightmap.width(), height: heightmap.height(), grid_size: land_grid_size, kind: ProvinceKind::Land};
+        let mut grids = self.seed_provinces(land_layout, &land_pixels, scales.as_ref(), &colors, Stage::Grids, Some(&rivers.major));
+        if settings.growth == Growth::Cost{
+            let costs = settings.growth_costs;
+            let elevation: Vec<f64> = heightmap.height_map.pixels().map(|pixel| pixel[0] as f64 / 256.0).collect();
+            growth::grow(&mut grids, &land_pixels, heightmap.width(), heightmap.height(), |from, to|{
+                let mut cost = 1.0 + costs.elevation * (elevation[from] - elevation[to]).abs();
+                if rivers.pixels[to] < river::WATER{
+                    cost += costs.river;
+                }
+                if terrain.terrain_map[from] != terrain.terrain_map[to]{
+                    cost += costs.terrain;
+                }
+                cost
+            });
+        }
+        for grid in grids.iter_mut(){
+            if grid.terrain_share(heightmap.width(), &terrain.terrain_map, &[Terrain::Mountains, Terrain::DesertMountain]) >= settings.impassable_mountain_share{
+                grid.kind = ProvinceKind::Impassable;
+            }
+        }
+        if grids.provinces().is_empty(){
+            return Err(Error::generation("provinces", "no grid cell has enough land to seed a province"))
+        }
+        let mut rng = random::stage_rng(settings.seed, Stage::RiverProvinces);
+        let mut river_provinces = Vec::<Grid>::collect_grids(Layout{kind: ProvinceKind::River, ..land_layout}, &rivers.major, None, &colors[grids.len()..], &mut rng);
+        river_provinces.pixels_to_provinces(heightmap.width(), land_grid_size, None);
+        grids.append(&mut river_provinces);
+        let mut water_pixels: Vec<bool> = heightmap.map_pixels.iter().map(|is_land| !is_land).collect();
+        for lake in heightmap.lakes.iter(){
+            for coords in lake.pixels.iter(){
+                water_pixels[coords.as_index(heightmap.width()) as usize] = false;
+            }
+        }
+        let sea_grid_size = settings.target_sea_zones.map_or(settings.sea_zone_grid_size, |target| self.fit_grid_size(heightmap, &water_pixels, None, &colors, target, Stage::SeaZones));
+        let sea_layout = Layout{width: heightmap.width(), height: heightmap.height(), grid_size: sea_grid_size, kind: ProvinceKind::Sea};
+        let mut sea_zones = self.seed_provinces(sea_layout, &water_pixels, None, &colors[grids.len()..], Stage::SeaZones, None);
+        grids.append(&mut sea_zones);
+        let lake_colors = &colors[grids.len()..];
+        for (i, lake) in heightmap.lakes.iter().enumerate(){
+            let mut grid = Grid::new(vec!(), lake.pixels.clone(), i as u32, lake_colors, ProvinceKind::Lake);
+            grid.province_pixels = lake.pixels.clone();
+            grids.push(grid);
+        }
+        if settings.border_jitter > 0.0{
+            let (width, height) = (heightmap.width(), heightmap.height());
+            let offset_x = noise::generate_noise_map(width, height, &settings.jitter_noise, settings.seed.wrapping_add(20));
+            let offset_y = noise::generate_noise_map(width, height, &settings.jitter_noise, settings.seed.wrapping_add(25));
+            jitter::jitter_borders(&mut grids, width, height, settings.border_jitter, &offset_x, &offset_y);
+        }
+        contiguity::make_contiguous(&mut grids, heightmap.width(), heightmap.height(), settings.min_province_size, &colors);
+        let graph = ProvinceGraph::new(&grids, heightmap.width(), heightmap.height());
+        let crossings = adjacencies::find_crossings(&grids, &graph, heightmap.width(), heightmap.height(), settings.max_crossing_distance);
+        Ok(Provinces{grids, land_grid_size, sea_grid_size, graph, crossings})
+    }
+    fn seeds(&self, layout: Layout, pixels: &Vec<bool>, scales: Option<&Vec<f64>>, stage: Stage) -> Vec<Coords>{
+        let settings = &self.settings;
+        let Layout{width, height, grid_size, ..} = layout;
+        let seeds = seeding::poisson_disk(pixels, width, height, seeding::radius(grid_size), scales, &mut random::stage_rng(settings.seed, stage));
+        match settings.seeding{
+            Seeding::Lloyd => seeding::lloyd(seeds, pixels, width, height, grid_size, scales, settings.lloyd_iterations),
+            _ => seeds
+        }
+    }
+    fn seed_provinces(&self, layout: Layout, pixels: &Vec<bool>, scales: Option<&Vec<f64>>, colors: &[u32], stage: Stage, barriers: Option<&Vec<bool>>) -> Vec<Grid>{
+        let Layout{width, grid_size, ..} = layout;
+        match self.settings.seeding{
+            Seeding::Grid => {
+                let mut rng = random::stage_rng(self.settings.seed, stage);
+                let mut grids = Vec::<Grid>::collect_grids(layout, pixels, scales, colors, &mut rng);
+                grids.pixels_to_provinces(width, grid_size, barriers);
+                grids
+            },
+            _ => {
+                let seeds = self.seeds(layout, pixels, scales, stage);
+                seeding::seeded_grids(&seeds, pixels, layout, colors, barriers)
+            }
+        }
+    }
+    // the grid size whose seeded cells come closest to the target, seed counts shrink as cells grow
+    fn fit_grid_size(&self, heightmap: &Heightmap, pixels: &Vec<bool>, scales: Option<&Vec<f64>>, colors: &[u32], target: u32, stage: Stage) -> u32{
+        let target = target as i64;
+        let (width, height) = (heightmap.width(), heightmap.height());
+        let mut counts = HashMap::new();
+        let mut seeded = |grid_size: u32| -> i64{
+            *counts.entry(grid_size).or_insert_with(||{
+                if self.settings.seeding != Seeding::Grid{
+                    return seeding::poisson_disk(pixels, width, height, seeding::radius(grid_size), scales, &mut random::stage_rng(self.settings.seed, stage)).len() as i64
+                }
+                let mut rng = random::stage_rng(self.settings.seed, stage);
+                let grids = Vec::<Grid>::collect_grids(Layout{width, height, grid_size, kind: ProvinceKind::Land}, pixels, scales, colors, &mut rng);
+                grids.iter().filter(|grid| !grid.starters.is_empty()).count() as i64
+            })
+        };
+        let (mut low, mut high) = (MIN_GRID_SIZE, width.min(height).max(MIN_GRID_SIZE));
+        while low < high{
+            let middle = (low + high) / 2;
+            if seeded(middle) > target{
+                low = middle + 1;
+            }
+            else{
+                high = middle;
+            }
+        }
+        if low > MIN_GRID_SIZE && (seeded(low - 1) - target).abs() < (seeded(low) - target).abs(){
+            low - 1
+        }
+        else{
+            low
+        }
+    }
+    pub fn titles(&self, heightmap: &Heightmap, terrain: &TerrainLayer, provinces: &Provinces) -> Titles{
+        let fertility: Vec<f64> = provinces.grids.provinces().iter().map(|grid|
+            grid.province_pixels.iter().map(|coords| terrain.terrain_map[coords.as_index(heightmap.width()) as usize].habitability()).sum::<f64>() / grid.province_pixels.len() as f64
+        ).collect();
+        let landmasses = landmass::detect(&heightmap.map_pixels, heightmap.width(), heightmap.height(), self.settings.titles.min_continent_size);
+        let continents: Vec<Option<usize>> = provinces.grids.provinces().iter().map(|grid|{
+            let mut counts = vec![0; landmasses.count()];
+            for coords in grid.province_pixels.iter(){
+                if let Some(continent) = landmasses.continents[coords.as_index(heightmap.width()) as usize]{
+                    counts[continent] += 1;
+                }
+            }
+            (0..counts.len()).filter(|&continent| counts[continent] > 0).max_by_key(|&continent| (counts[continent], std::cmp::Reverse(continent)))
+        }).collect();
+        let mut rng = random::stage_rng(self.settings.seed, Stage::Titles);
+        let mut tree = TitleTree::build(&provinces.grids, &provinces.graph, &provinces.crossings, &fertility, &continents, &self.settings.titles, &mut rng);
+        let mut generator = NameGenerator::new(random::stage_rng(self.settings.seed, Stage::Names));
+        tree.assign_names(&mut generator);
+        let mut province_names = vec![None; provinces.grids.provinces().len()];
+        for title in tree.titles.iter(){
+            if let Some(province) = title.province{
+                province_names[province as usize - 1] = Some(title.name.clone());
+            }
+        }
+        let province_names = province_names.into_iter().map(|name| name.unwrap_or_else(|| generator.name())).collect();
+        let island_crossings = adjacencies::island_crossings(&provinces.grids, &provinces.graph, heightmap.width(), heightmap.height(), &tree.island_links);
+        Titles{landmasses, tree, island_crossings, province_names}
+    }
+    pub fn run(&self) -> Result<GeneratedMap>{
+        let heightmap = self.heightmap()?;
+        let climate = self.climate(&heightmap);
+        let terrain = self.terrain(&heightmap, &climate);
+        let rivers = self.rivers(&heightmap, &climate);
+        let provinces = self.provinces(&heightmap, &terrain, &rivers)?;
+        let titles = self.titles(&heightmap, &terrain, &provinces);
+        Ok(GeneratedMap{heightmap, climate, terrain, rivers, provinces, titles})
+    }
+    pub fn export(&self, map: &GeneratedMap, output: &OutputRoot) -> Result<()>{
+        let (width, height) = (map.heightmap.width(), map.heightmap.height());
+        output.save_image("map_data/heightmap.png", |path| map.heightmap.height_map.save(path))?;
+        output.save_image("map_data/moisturemap.png", |path| map.climate.moisture_map.save(path))?;
+        output.save_image("map_data/temperaturemap.png", |path| map.climate.temperature_map.save(path))?;
+
+        terrain::save_masks(&map.terrain.masks, output)?;
+        let terrain_image = map.terrain.terrain_map.to_image(width);
+        output.save_image("map_data/terrainmap.png", |path| terrain_image.save(path))?;
+
+        let mut flatmap: im::RgbImage = im::ImageBuffer::new(width, height);
+        for (pixel, is_land) in map.heightmap.map_pixels.iter().enumerate(){
+            let coords = (pixel as u32).as_coords(width);
+            if *is_land{
+                flatmap.put_pixel(coords.x, coords.y, LAND_FLAT_COLOR);
+            }
+            else{
+                flatmap.put_pixel(coords.x, coords.y, WATER_FLAT_COLOR);
+            }
+        }
+        output.save_image("gfx/map/terrain/flatmap.png", |path| flatmap.save(path))?;
+
+        river::save_rivers(&map.rivers.pixels, width, height, &output.path("map_data/rivers.png"))?;
+
+        let grids = &map.provinces.grids;
+        output.save_image("map_data/provinces.png", |path| grids.province_map(width, height).save(path))?;
+        output.write_file("map_data/definition.csv", &grids.definition(&map.titles.province_names))?;
+        let mut crossings = map.provinces.crossings.clone();
+        crossings.extend_from_slice(&map.titles.island_crossings);
+        crossings.sort_by_key(|crossing| (crossing.from, crossing.to));
+        output.write_file("map_data/adjacencies.csv", &adjacencies::adjacencies_csv(&crossings, height))?;
+        output.write_file("map_data/province_graph.csv", &map.provinces.graph.to_csv())?;
+        output.write_file("map_data/province_graph.json", &map.provinces.graph.to_json())?;
+        output.write_file("map_data/default.map", &default_map::default_map(grids))?;
+        output.write_file("common/landed_titles/00_landed_titles.txt", &map.titles.tree.landed_titles())?;
+        output.write_file("common/province_terrain/00_province_terrain.txt", &grids.province_terrain(width, &map.terrain.terrain_map))?;
+        let title_entries = localization::title_entries(&map.titles.tree);
+        let province_entries = localization::province_entries(&map.titles.province_names);
+        for language in self.settings.languages(){
+            output.create_folder(&format!("localization/{}", language))?;
+            output.write_file(&localization::path(language, localization::TITLES_FILE), &localization::localization(language, &title_entries))?;
+            output.write_file(&localization::path(language, localization::PROVINCES_FILE), &localization::localization(language, &province_entries))?;
+        }
+
+        let descriptor = &self.settings.descriptor;
+        output.save_image("thumbnail.png", |path| descriptor::thumbnail(&terrain_image).save(path))?;
+        output.write_file("descriptor.mod", &descriptor.to_descriptor(None))?;
+        let absolute_root = output.absolute_root()?;
+        output.write_outer_descriptor(&descriptor.to_descriptor(Some(&absolute_root.to_string_lossy().replace('\\', "/"))))?;
+        Ok(())
+    }
+}
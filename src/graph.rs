@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::grid::{Grid, GridVector, ProvinceKind};
+use crate::numastype::NumAsType;
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind{
+    Land,
+    Sea,
+    Lake,
+    River
+}
+impl EdgeKind{
+    fn between(a: ProvinceKind, b: ProvinceKind) -> Self{
+        match (a, b){
+            (ProvinceKind::Sea, _) | (_, ProvinceKind::Sea) => EdgeKind::Sea,
+            (ProvinceKind::Lake, _) | (_, ProvinceKind::Lake) => EdgeKind::Lake,
+            (ProvinceKind::River, _) | (_, ProvinceKind::River) => EdgeKind::River,
+            _ => EdgeKind::Land
+        }
+    }
+    fn name(&self) -> &'static str{
+        match self{
+            EdgeKind::Land => "land",
+            EdgeKind::Sea => "sea",
+            EdgeKind::Lake => "lake",
+            EdgeKind::River => "river"
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct ProvinceNode{
+    pub id: u32,
+    pub kind: ProvinceKind,
+    pub pixels: usize,
+    pub coastal: bool,
+    pub lakeside: bool
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize)]
+pub struct Edge{
+    pub from: u32,
+    pub to: u32,
+    pub kind: EdgeKind,
+    pub border: u32
+}
+
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct ProvinceGraph{
+    pub nodes: Vec<ProvinceNode>,
+    pub edges: Vec<Edge>,
+    #[serde(skip)]
+    lookup: HashMap<(u32, u32), usize>
+}
+impl ProvinceGraph{
+    pub fn new(grids: &Vec<Grid>, width: u32, height: u32) -> Self{
+        let kinds: Vec<ProvinceKind> = grids.provinces().iter().map(|grid| grid.kind).collect();
+        ProvinceGraph::from_raster(&grids.province_raster(width, height), &kinds, width, height)
+    }
+    // raster values are indices into kinds, province ids are those indices plus one
+    pub fn from_raster(raster: &[Option<usize>], kinds: &[ProvinceKind], width: u32, height: u32) -> Self{
+        let mut nodes: Vec<ProvinceNode> = kinds.iter().enumerate().map(|(i, &kind)|
+            ProvinceNode{id: i as u32 + 1, kind, pixels: 0, coastal: false, lakeside: false}
+        ).collect();
+        let mut borders: HashMap<(u32, u32), u32> = HashMap::new();
+        for index in 0..raster.len(){
+            let province = match raster[index]{
+                Some(province) => province,
+                None => continue
+            };
+            nodes[province].pixels += 1;
+            let coords = (index as u32).as_coords(width);
+            let mut neighbours = vec!();
+            if coords.x + 1 < width{
+                neighbours.push(index + 1);
+            }
+            if coords.y + 1 < height{
+                neighbours.push(index + width as usize);
+            }
+            for neighbour in neighbours{
+                match raster[neighbour]{
+                    Some(other) if other != province => {
+                        let pair = (province.min(other) as u32 + 1, province.max(other) as u32 + 1);
+                        *borders.entry(pair).or_insert(0) += 1;
+                    },
+                    _ => {}
+                }
+            }
+        }
+        let mut pairs: Vec<((u32, u32), u32)> = borders.into_iter().collect();
+        pairs.sort();
+        let mut edges = vec!();
+        let mut lookup = HashMap::new();
+        for ((from, to), border) in pairs{
+            let (a, b) = (nodes[from as usize - 1].kind, nodes[to as usize - 1].kind);
+            for (node, other) in [(from, b), (to, a)].iter(){
+                let node = &mut nodes[*node as usize - 1];
+                if !node.kind.is_water(){
+                    node.coastal |= *other == ProvinceKind::Sea;
+                    node.lakeside |= *other == ProvinceKind::Lake;
+                }
+            }
+            lookup.insert((from, to), edges.len());
+            edges.push(Edge{from, to, kind: EdgeKind::between(a, b), border});
+        }
+        ProvinceGraph{nodes, edges, lookup}
+    }
+    pub fn node(&self, id: u32) -> &ProvinceNode{
+        &self.nodes[id as usize - 1]
+    }
+    pub fn edge(&self, a: u32, b: u32) -> Option<&Edge>{
+        self.lookup.get(&(a.min(b), a.max(b))).map(|&i| &self.edges[i])
+    }
+    pub fn are_adjacent(&self, a: u32, b: u32) -> bool{
+        self.edge(a, b).is_some()
+    }
+    pub fn shared_border(&self, a: u32, b: u32) -> u32{
+        self.edge(a, b).map_or(0, |edge| edge.border)
+    }
+    pub fn neighbours(&self, id: u32) -> Vec<u32>{
+        self.edges.iter().filter_map(|edge|
+            if edge.from == id {Some(edge.to)} else if edge.to == id {Some(edge.from)} else {None}
+        ).collect()
+    }
+    pub fn to_csv(&self) -> String{
+        let mut csv = String::from("from;to;type;border;from_coastal;to_coastal\n");
+        for edge in self.edges.iter(){
+            csv.push_str(&format!(
+                "{};{};{};{};{};{}\n",
+                edge.from, edge.to, edge.kind.name(), edge.border, self.node(edge.from).coastal, self.node(edge.to).coastal
+            ));
+        }
+        csv
+    }
+    pub fn to_json(&self) -> String{
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
@@ -1,14 +1,16 @@
 use std::cmp;
-use crate::{BLACK, PROVINCE_GRID_SIZE};
+use serde::Serialize;
+use crate::BLACK;
 use crate::numastype::NumAsType;
-use rand::thread_rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use rand::Rng;
 use crate::terrain::Terrain;
-use std::fs;
-use std::io::Write;
 
-#[derive(PartialEq, Clone, Copy)]
+const STARTERS: usize = 4;
+const MAX_STARTERS: usize = 12;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub struct Coords{pub x: u32, pub y: u32}
 impl Coords{
     pub fn new(x: u32, y: u32) -> Self{
@@ -28,25 +30,62 @@ impl Coords{
     pub fn as_index(&self, width: u32) -> u32{
         self.x + self.y*width
     }
+    pub fn line_of_sight(&self, other: &Coords, width: u32, barriers: &Vec<bool>) -> bool{
+        let (dx, dy) = (other.x as f64 - self.x as f64, other.y as f64 - self.y as f64);
+        let steps = dx.abs().max(dy.abs()) as u32;
+        for step in 1..steps{
+            let t = step as f64 / steps as f64;
+            let coords = Coords::new((self.x as f64 + dx * t).round() as u32, (self.y as f64 + dy * t).round() as u32);
+            if barriers[coords.as_index(width) as usize]{
+                return false
+            }
+        }
+        true
+    }
+}
+#[derive(PartialEq, Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvinceKind{
+    Land,
+    Sea,
+    Lake,
+    River,
+    Impassable
+}
+impl ProvinceKind{
+    pub fn is_water(&self) -> bool{
+        matches!(self, ProvinceKind::Sea | ProvinceKind::Lake | ProvinceKind::River)
+    }
+}
+
+// the map size, grid size and kind of the provinces being seeded
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Layout{
+    pub width: u32,
+    pub height: u32,
+    pub grid_size: u32,
+    pub kind: ProvinceKind
 }
+
 #[derive(PartialEq, Clone)]
 pub struct Grid{
     pub starters: Vec<Coords>,
     pub land_pixels: Vec<Coords>,
     pub province_pixels: Vec<Coords>,
     pub color: im::Rgb<u8>,
-    pub index: u32
+    pub index: u32,
+    pub kind: ProvinceKind
 }
 
 impl Grid{
-    pub fn new(starters: Vec<Coords>, land_pixels: Vec<Coords>, index: u32, colors: &Vec<u32>) -> Self{
+    pub fn new(starters: Vec<Coords>, land_pixels: Vec<Coords>, index: u32, colors: &[u32], kind: ProvinceKind) -> Self{
         Grid{
-            starters, land_pixels, province_pixels: vec!(), color: colors[index as usize].as_rgb8(), index
+            starters, land_pixels, province_pixels: vec!(), color: colors[index as usize].as_rgb8(), index, kind
         }
     }
-    pub fn empty(land_pixels: Vec<Coords>, index: u32) -> Self{
+    pub fn empty(land_pixels: Vec<Coords>, index: u32, kind: ProvinceKind) -> Self{
         Grid{
-            starters: vec!(), land_pixels, province_pixels: vec!(), color: BLACK, index
+            starters: vec!(), land_pixels, province_pixels: vec!(), color: BLACK, index, kind
         }
     }
     pub fn get_neighbours(&self, width: u32, grids: &Vec<Grid>) -> Vec<usize>{
@@ -76,62 +115,100 @@ impl Grid{
             .cmp(&terrains.iter().filter(|&n| n == *b).count())
         ).unwrap()
     }
+    pub fn terrain_share(&self, width: u32, terrain_map: &Vec<Terrain>, terrains: &[Terrain]) -> f64{
+        if self.province_pixels.len() == 0{
+            return 0.0
+        }
+        let count = self.province_pixels.iter().filter(|pixel| terrains.contains(&terrain_map[pixel.as_index(width) as usize])).count();
+        count as f64 / self.province_pixels.len() as f64
+    }
+}
+
+// multi_distance weighs the starters by 1, 1/2, 1/4 and so on, the count whose total weight is closest to the
+// default weight divided by the scale makes the cell reach about scale times as far
+fn starter_count(scale: f64) -> usize{
+    let weight = |count: usize| 2.0 * (1.0 - 0.5f64.powi(count as i32));
+    let target = weight(STARTERS) / scale;
+    (1..=MAX_STARTERS).min_by(|a, b| (weight(*a) - target).abs().partial_cmp(&(weight(*b) - target).abs()).unwrap()).unwrap()
+}
+
+// rows are shifted by up to one cell, so every row needs an extra column to reach the right edge
+fn grid_columns(width: u32, grid_size: u32) -> u32{
+    (width + grid_size - 1)/grid_size + 1
 }
 
 pub trait GridVector{
-    fn collect_grids(width:u32, height:u32, map_pixels: &Vec<bool>, colors: &Vec<u32>) -> Self;
-    fn pixels_to_provinces(&mut self, width: u32);
-    fn save_to_files(&self, width:u32, map: &mut im::RgbImage, terrain_map: &Vec::<Terrain>);
+    fn collect_grids(layout: Layout, map_pixels: &Vec<bool>, scales: Option<&Vec<f64>>, colors: &[u32], rng: &mut StdRng) -> Self;
+    fn pixels_to_provinces(&mut self, width: u32, grid_size: u32, barriers: Option<&Vec<bool>>);
+    fn provinces(&self) -> Vec<&Grid>;
+    fn province_ids(&self, kind: ProvinceKind) -> Vec<u32>;
+    fn province_map(&self, width: u32, height: u32) -> im::RgbImage;
+    fn province_raster(&self, width: u32, height: u32) -> Vec<Option<usize>>;
+    fn owners(&self, width: u32, height: u32) -> Vec<Option<usize>>;
+    fn assign_owners(&mut self, owners: &[Option<usize>], width: u32);
+    fn definition(&self, names: &[String]) -> String;
+    fn province_terrain(&self, width: u32, terrain_map: &Vec::<Terrain>) -> String;
 }
 impl GridVector for Vec::<Grid>{
-    fn collect_grids(width:u32, height:u32, map_pixels: &Vec<bool>, colors: &Vec<u32>) -> Self{
-        let mut rng = thread_rng();
+    // cells get more starters where scales are small, which shrinks their provinces since distances add up over all starters
+    fn collect_grids(layout: Layout, map_pixels: &Vec<bool>, scales: Option<&Vec<f64>>, colors: &[u32], rng: &mut StdRng) -> Self{
+        let Layout{width, height, grid_size, kind} = layout;
         let mut grids: Vec<Grid> = vec!();
-        for base_y in 0..height/PROVINCE_GRID_SIZE{
-            let offset = rng.gen_range(0..PROVINCE_GRID_SIZE) as i64;
-            for base_x in 0..width/PROVINCE_GRID_SIZE+1{
+        for base_y in 0..(height + grid_size - 1)/grid_size{
+            let offset = rng.gen_range(0..grid_size) as i64;
+            for base_x in 0..grid_columns(width, grid_size){
                 let mut land_pixels:Vec<Coords> = vec!();
-                for x in 0..PROVINCE_GRID_SIZE{
-                    let coord_x = (x+base_x*PROVINCE_GRID_SIZE) as i64 - offset;
+                for x in 0..grid_size{
+                    let coord_x = (x+base_x*grid_size) as i64 - offset;
                     if coord_x < 0 || coord_x >= width as i64{
                         continue
                     }
-                    for y in 0..PROVINCE_GRID_SIZE{
-                        let coords = Coords{x: coord_x as u32, y: y+base_y*PROVINCE_GRID_SIZE as u32};
+                    for y in 0..cmp::min(grid_size, height - base_y*grid_size){
+                        let coords = Coords{x: coord_x as u32, y: y+base_y*grid_size};
                         let index = coords.as_index(width);
                         if map_pixels[index as usize]{
                             land_pixels.push(coords);
                         }
                     }
                 }
-                let index = Coords::new(base_x, base_y).as_index(width/PROVINCE_GRID_SIZE+1);
+                let index = Coords::new(base_x, base_y).as_index(grid_columns(width, grid_size));
                 if land_pixels.len() > 1{
+                    let count = match scales{
+                        Some(scales) => starter_count(land_pixels.iter().map(|coords| scales[coords.as_index(width) as usize]).sum::<f64>() / land_pixels.len() as f64),
+                        None => STARTERS
+                    };
                     grids.push(Grid::new(
-                        vec!(*land_pixels.choose(&mut rng).unwrap(), *land_pixels.choose(&mut rng).unwrap(), *land_pixels.choose(&mut rng).unwrap(), *land_pixels.choose(&mut rng).unwrap()),
+                        (0..count).map(|_| *land_pixels.choose(rng).unwrap()).collect(),
                         land_pixels,
                         index,
-                        &colors
+                        colors,
+                        kind
                     ));
                 }
                 else{
-                    grids.push(Grid::empty(land_pixels, index));
+                    grids.push(Grid::empty(land_pixels, index, kind));
                 }
             }
         }
         grids
     }
-    fn pixels_to_provinces(&mut self, width: u32){
+    fn pixels_to_provinces(&mut self, width: u32, grid_size: u32, barriers: Option<&Vec<bool>>){
         for i in 0..self.len(){
             if self[i].land_pixels.len() == 0{
                 continue
             }
             let grid = self[i].clone();
-            let neighbours = grid.get_neighbours(width/PROVINCE_GRID_SIZE+1, &self);
+            let neighbours = grid.get_neighbours(grid_columns(width, grid_size), &self);
             if neighbours.len() == 0{
                 continue
             }
             for coords in grid.land_pixels{
-                let index = *neighbours.iter().min_by(|a, b|
+                let visible: Vec<usize> = match barriers{
+                    Some(barriers) => neighbours.iter().copied().filter(|&neighbour| coords.line_of_sight(&self[neighbour].starters[0], width, barriers)).collect(),
+                    None => vec!()
+                };
+                let candidates = if visible.is_empty() {&neighbours} else {&visible};
+                let index = *candidates.iter().min_by(|a, b|
                     coords.multi_distance(&self[**a].starters)
                     .partial_cmp(&coords.multi_distance(&self[**b].starters)).unwrap()
                 ).unwrap();
@@ -139,44 +216,67 @@ impl GridVector for Vec::<Grid>{
             }
         }
     }
-    fn save_to_files(&self, width:u32, map: &mut im::RgbImage, terrain_map: &Vec::<Terrain>){
-        let mut definition = String::from("0;0;0;0;x;x;\n");
-        let mut titles = String::from("e_test = { color = { 0 0 0 } color2 = { 255 255 255 } capital = c_test_1 k_test = { color = { 0 0 0 } color2 = { 255 255 255 \n");
-        let mut province_terrain = String::from("default=plains\n");
-        let mut index = 0;
+    fn provinces(&self) -> Vec<&Grid>{
+        self.iter().filter(|grid| grid.province_pixels.len() > 0).collect()
+    }
+    fn province_ids(&self, kind: ProvinceKind) -> Vec<u32>{
+        self.provinces().iter().enumerate().filter(|(_, grid)| grid.kind == kind).map(|(i, _)| i as u32 + 1).collect()
+    }
+    fn province_map(&self, width: u32, height: u32) -> im::RgbImage{
+        let mut map: im::RgbImage = im::ImageBuffer::from_pixel(width, height, BLACK);
         for grid in self.iter(){
-            if grid.province_pixels.len() == 0{
-                continue
+            for coords in grid.province_pixels.iter(){
+                map.put_pixel(coords.x, coords.y, grid.color);
             }
-            let color = grid.color;
-            index += 1;
-            let small_index = index - 1;
-            definition.push_str(&format!("{index};{};{};{};b_test_{index};x;\n", color[0], color[1], color[2], index=index));
-            if small_index%3 == 0{
-                if small_index != 0{
-                    titles.push_str(&"}");
-                }
-                if small_index/3%3 == 0{
-                    titles.push_str(&format!(" }} \n d_test_{index} = {{ color = {{ {} {} {} }} color2 = {{ 255 255 255 }} capital = c_test_{index}\n", color[0], color[1], color[2], index=index));
-                }
-                titles.push_str(&format!("c_test_{index} = {{ color = {{ {} {} {} }} color2 = {{ 255 255 255 }}\n", color[0], color[1], color[2], index=index));
+        }
+        map
+    }
+    fn province_raster(&self, width: u32, height: u32) -> Vec<Option<usize>>{
+        let mut raster = vec![None; (width*height) as usize];
+        for (i, grid) in self.provinces().iter().enumerate(){
+            for coords in grid.province_pixels.iter(){
+                raster[coords.as_index(width) as usize] = Some(i);
             }
-            titles.push_str(&format!("b_test_{index} = {{ province = {index} color = {{ {} {} {} }} color2 = {{ 255 255 255 }} }}\n", color[0], color[1], color[2], index=index));
-            let terrain = grid.most_common_terrain(width, &terrain_map);
-            province_terrain.push_str(&format!("{index} = {}\n", terrain.to_string().to_lowercase(), index=index));
+        }
+        raster
+    }
+    // like province_raster but indexed by grid, so empty grids keep their place
+    fn owners(&self, width: u32, height: u32) -> Vec<Option<usize>>{
+        let mut owners = vec![None; (width*height) as usize];
+        for (i, grid) in self.iter().enumerate(){
             for coords in grid.province_pixels.iter(){
-                map.put_pixel(coords.x, coords.y, color);
+                owners[coords.as_index(width) as usize] = Some(i);
             }
         }
-        titles.push_str(&"} } } }");
-        
-        map.save("mod/map_data/provinces.png").unwrap();
-
-        let mut file = fs::File::create("mod/map_data/definition.csv").unwrap();
-        file.write_all(definition.as_bytes()).unwrap();
-        let mut file = fs::File::create("mod/common/landed_titles/00_landed_titles.txt").unwrap();
-        file.write_all(titles.as_bytes()).unwrap();
-        let mut file = fs::File::create("mod/common/province_terrain/00_province_terrain.txt").unwrap();
-        file.write_all(province_terrain.as_bytes()).unwrap();
-    }
-}
\ No newline at end of file
+        owners
+    }
+    fn assign_owners(&mut self, owners: &[Option<usize>], width: u32){
+        for grid in self.iter_mut(){
+            grid.province_pixels.clear();
+        }
+        for (index, owner) in owners.iter().enumerate(){
+            if let Some(owner) = owner{
+                self[*owner].province_pixels.push((index as u32).as_coords(width));
+            }
+        }
+    }
+    fn definition(&self, names: &[String]) -> String{
+        let mut definition = String::from("0;0;0;0;x;x;\n");
+        for (i, grid) in self.provinces().iter().enumerate(){
+            let color = grid.color;
+            definition.push_str(&format!("{};{};{};{};{};x;\n", i + 1, color[0], color[1], color[2], names[i]));
+        }
+        definition
+    }
+    fn province_terrain(&self, width: u32, terrain_map: &Vec::<Terrain>) -> String{
+        let mut province_terrain = String::from("default=plains\n");
+        for (i, grid) in self.provinces().iter().enumerate(){
+            if grid.kind.is_water(){
+                continue
+            }
+            let terrain = grid.most_common_terrain(width, &terrain_map);
+            province_terrain.push_str(&format!("{index} = {}\n", terrain.to_string().to_lowercase(), index=i + 1));
+        }
+        province_terrain
+    }
+}
@@ -0,0 +1,73 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use serde::{Deserialize, Serialize};
+use crate::grid::{Grid, GridVector};
+use crate::water::neighbour_indices;
+
+pub const GROWTHS:[&str; 2] = ["distance", "cost"];
+// costs are summed as integers so equal paths resolve the same way on every run
+const COST_PRECISION: f64 = 1000.0;
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Growth{
+    Distance,
+    Cost
+}
+impl Growth{
+    pub fn from_name(name: &str) -> Option<Self>{
+        match name{
+            "distance" => Some(Growth::Distance),
+            "cost" => Some(Growth::Cost),
+            _ => None
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrowthCosts{
+    pub elevation: f64,
+    pub river: f64,
+    pub terrain: f64
+}
+impl Default for GrowthCosts{
+    fn default() -> Self{
+        GrowthCosts{elevation: 1.0, river: 20.0, terrain: 5.0}
+    }
+}
+
+// regrows provinces from their starters with a multi-source dijkstra, pixels no starter can reach keep their owner
+pub fn grow<F: Fn(usize, usize) -> f64>(grids: &mut Vec<Grid>, pixels: &Vec<bool>, width: u32, height: u32, step_cost: F){
+    let mut grown = grids.owners(width, height);
+    let mut cost = vec![u64::MAX; pixels.len()];
+    let mut queue = BinaryHeap::new();
+    for (i, grid) in grids.iter().enumerate(){
+        for starter in grid.starters.iter(){
+            let index = starter.as_index(width) as usize;
+            if pixels[index] && cost[index] > 0{
+                cost[index] = 0;
+                queue.push(Reverse((0, i, index)));
+            }
+        }
+    }
+    let mut done = vec![false; pixels.len()];
+    while let Some(Reverse((current, grid, index))) = queue.pop(){
+        if done[index]{
+            continue
+        }
+        done[index] = true;
+        grown[index] = Some(grid);
+        for neighbour in neighbour_indices(index, width, height){
+            if !pixels[neighbour] || done[neighbour]{
+                continue
+            }
+            let next = current + (step_cost(index, neighbour) * COST_PRECISION) as u64;
+            if next < cost[neighbour]{
+                cost[neighbour] = next;
+                queue.push(Reverse((next, grid, neighbour)));
+            }
+        }
+    }
+    grids.assign_owners(&grown, width);
+}
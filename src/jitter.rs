@@ -0,0 +1,26 @@
+use crate::grid::{Grid, GridVector, ProvinceKind};
+use crate::generator::NoiseMap;
+use crate::numastype::NumAsType;
+
+// moves every pixel to the province found at a noise-displaced position, as long as that province is of the same kind,
+// lakes are whole water bodies and keep their pixels
+pub fn jitter_borders(grids: &mut Vec<Grid>, width: u32, height: u32, amplitude: f64, offset_x: &NoiseMap, offset_y: &NoiseMap){
+    let owner = grids.owners(width, height);
+    let offset = |map: &NoiseMap, x: u32, y: u32| (map.get_pixel(x, y)[0] as f64 / 65535.0 - 0.5) * 2.0 * amplitude;
+    let mut jittered = owner.clone();
+    for (index, current) in owner.iter().enumerate(){
+        let current = match current{
+            Some(current) if grids[*current].kind != ProvinceKind::Lake => *current,
+            _ => continue
+        };
+        let coords = (index as u32).as_coords(width);
+        let x = (coords.x as f64 + offset(offset_x, coords.x, coords.y)).round().max(0.0).min(width as f64 - 1.0) as u32;
+        let y = (coords.y as f64 + offset(offset_y, coords.x, coords.y)).round().max(0.0).min(height as f64 - 1.0) as u32;
+        if let Some(other) = owner[(x + y*width) as usize]{
+            if grids[other].kind == grids[current].kind{
+                jittered[index] = Some(other);
+            }
+        }
+    }
+    grids.assign_owners(&jittered, width);
+}
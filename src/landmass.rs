@@ -0,0 +1,32 @@
+use crate::water;
+
+pub struct Landmasses{
+    pub continents: Vec<Option<usize>>,
+    pub sizes: Vec<usize>,
+    pub islands: usize
+}
+impl Landmasses{
+    pub fn count(&self) -> usize{
+        self.sizes.len()
+    }
+}
+
+// connected land areas of at least min_size pixels become continents, the largest one always does
+pub fn detect(map_pixels: &Vec<bool>, width: u32, height: u32, min_size: u32) -> Landmasses{
+    let water_pixels: Vec<bool> = map_pixels.iter().map(|is_land| !is_land).collect();
+    let mut bodies = water::collect_water_bodies(&water_pixels, width, height);
+    bodies.sort_by_key(|body| std::cmp::Reverse(body.pixels.len()));
+    let mut continents = vec![None; map_pixels.len()];
+    let mut sizes = vec!();
+    for (i, body) in bodies.iter().enumerate(){
+        if i > 0 && body.pixels.len() < min_size as usize{
+            break
+        }
+        for coords in body.pixels.iter(){
+            continents[coords.as_index(width) as usize] = Some(i);
+        }
+        sizes.push(body.pixels.len());
+    }
+    let islands = bodies.len() - sizes.len();
+    Landmasses{continents, sizes, islands}
+}
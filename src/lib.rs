@@ -0,0 +1,37 @@
+extern crate image as im;
+
+pub mod grid;
+pub mod numastype;
+pub mod noise;
+pub mod terrain;
+mod religion;
+pub mod settings;
+pub mod random;
+pub mod validate;
+pub mod generator;
+pub mod error;
+pub mod output;
+pub mod descriptor;
+pub mod default_map;
+pub mod water;
+pub mod river;
+pub mod adjacencies;
+pub mod graph;
+pub mod contiguity;
+pub mod seeding;
+pub mod growth;
+pub mod jitter;
+pub mod titles;
+pub mod landmass;
+pub mod names;
+pub mod localization;
+pub use generator::*;
+
+pub const NUM_OF_COLORS:u32 = (255 as u32).pow(3);
+pub const LAND_MAP: &str = "from/random.png";
+pub const BLACK:im::Rgb<u8> = im::Rgb([0, 0, 0]);
+pub const WHITE:im::Rgb<u8> = im::Rgb([255, 255, 255]);
+pub const PINK:im::Rgb<u8> = im::Rgb([255, 0, 128]);
+pub const LAND_FLAT_COLOR:im::Rgb<u8> = im::Rgb([170, 160, 140]);
+pub const WATER_FLAT_COLOR:im::Rgb<u8> = im::Rgb([130, 130, 120]);
+pub const FOLDERS:[&str; 7] = ["map_data", "common/landed_titles", "common/province_terrain", "gfx/map/terrain", "history/provinces", "history/titles", "localization"];
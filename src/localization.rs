@@ -0,0 +1,65 @@
+use crate::names;
+use crate::titles::{Rank, TitleTree};
+
+pub const EXTRA_LANGUAGES:[&str; 6] = ["french", "german", "spanish", "russian", "korean", "simp_chinese"];
+pub const TITLES_FILE: &str = "random_map_titles";
+pub const PROVINCES_FILE: &str = "random_map_provinces";
+const BOM: char = '\u{feff}';
+
+// localization/{language}/{file}_l_{language}.yml, the suffix is how the game picks the language
+pub fn path(language: &str, file: &str) -> String{
+    format!("localization/{language}/{file}_l_{language}.yml", language=language, file=file)
+}
+
+// titles and their adjectives, baronies have no adjective in the base game either
+pub fn title_entries(tree: &TitleTree) -> Vec<(String, String)>{
+    let mut entries = vec!();
+    for title in tree.titles.iter(){
+        entries.push((title.key.clone(), title.name.clone()));
+        if title.rank != Rank::Barony{
+            entries.push((format!("{}_adj", title.key), names::adjective(&title.name)));
+        }
+    }
+    entries
+}
+
+// every province including sea zones, lakes and river provinces, keyed by id like the base game
+pub fn province_entries(province_names: &[String]) -> Vec<(String, String)>{
+    province_names.iter().enumerate().map(|(i, name)| (format!("PROV{}", i + 1), name.clone())).collect()
+}
+
+pub fn localization(language: &str, entries: &[(String, String)]) -> String{
+    let mut localization = format!("{}l_{}:\n", BOM, language);
+    for (key, value) in entries.iter(){
+        localization.push_str(&format!(" {}:0 \"{}\"\n", key, value.replace('"', "\\\"")));
+    }
+    localization
+}
+
+pub fn has_header(contents: &str, language: &str) -> bool{
+    contents.starts_with(&format!("{}l_{}:", BOM, language))
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn files_start_with_a_bom_and_the_language_header(){
+        let contents = localization("english", &[(String::from("c_merg"), String::from("Merg"))]);
+        assert!(contents.starts_with("\u{feff}l_english:\n"));
+        assert!(has_header(&contents, "english"));
+        assert!(!has_header(&contents, "french"));
+    }
+
+    #[test]
+    fn entries_use_version_zero(){
+        let contents = localization("french", &[(String::from("PROV1"), String::from("Laont"))]);
+        assert_eq!(contents, "\u{feff}l_french:\n PROV1:0 \"Laont\"\n");
+    }
+
+    #[test]
+    fn paths_end_in_the_language_suffix(){
+        assert_eq!(path("german", TITLES_FILE), "localization/german/random_map_titles_l_german.yml");
+    }
+}
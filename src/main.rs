@@ -1,87 +1,102 @@
-extern crate image as im;
-
-use rand::seq::SliceRandom;
-//use rand::Rng;
-use rand::thread_rng;
-use std::time::SystemTime;
 use std::path::Path;
-use std::fs;
-
-mod grid;
-use grid::Grid;
-use grid::GridVector;
-mod numastype;
-use numastype::NumAsType;
-mod noise;
-mod terrain;
-use crate::terrain::*;
-mod religion;
+use std::time::SystemTime;
+use std::process;
+use clap::Parser;
+use ck3_random_map::{GeneratedMap, MapGenerator};
+use ck3_random_map::error::{Error, Result};
+use ck3_random_map::grid::ProvinceKind;
+use ck3_random_map::output::{OutputMode, OutputRoot};
+use ck3_random_map::settings::Settings;
+use ck3_random_map::terrain::{Terrain, TerrainVector};
+use ck3_random_map::titles::Rank;
+use ck3_random_map::validate;
 
-const NUM_OF_COLORS:u32 = (255 as u32).pow(3);
-const LAND_MAP: &str = "from/random.png";
-const PROVINCE_GRID_SIZE:u32 = 64;
-const LAND_COLOR: u16 = 4352;
-const BLACK:im::Rgb<u8> = im::Rgb([0, 0, 0]);
-const WHITE:im::Rgb<u8> = im::Rgb([255, 255, 255]);
-const PINK:im::Rgb<u8> = im::Rgb([255, 0, 128]);
-const LAND_FLAT_COLOR:im::Rgb<u8> = im::Rgb([170, 160, 140]);
-const WATER_FLAT_COLOR:im::Rgb<u8> = im::Rgb([130, 130, 120]);
-const FOLDERS:[&str; 4] = ["map_data", "common/landed_titles", "common/province_terrain", "gfx/map/terrain"];
-const ROOT_FOLDER:&str = "mod";
-const SEED: u32 = 9787;
+mod cli;
+use cli::{Cli, Command};
 
 fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command{
+        Command::Generate{map, force, clean} => map.to_settings().and_then(|settings| generate(settings, cli::output_mode(force, clean))),
+        Command::Preview{map, output} => map.to_settings().and_then(|settings| preview(settings, &output)),
+        Command::Validate{root_folder} => validate(&root_folder),
+        Command::Stats(map) => map.to_settings().and_then(stats)
+    };
+    if let Err(error) = result{
+        eprintln!("Error: {}", error);
+        match error{
+            Error::Config(_) => process::exit(2),
+            _ => process::exit(1)
+        }
+    }
+}
+
+fn generate(settings: Settings, mode: OutputMode) -> Result<()>{
     let start_time = SystemTime::now();
-    let height_map = noise::generate_noise_map(1024, 512, 7, 2.12323, 0.5, 1.0, 3.0, -0.2, 5.0, SEED);
-    //let height_map = im::open(LAND_MAP).unwrap().into_luma16();
-    let (width, height) = (height_map.width(), height_map.height());
-    let moisture_map = noise::generate_noise_map(width, height, 3, 2.02345, 0.5, 0.5, 3.0, -0.9, 3.5, SEED + 10);
-    let temperature_map = noise::generate_noise_map(width, height, 3, 2.201348, 0.5, 0.5, 3.0, -0.9, 3.5, SEED + 15);
-    let mut colors: Vec<u32> = (0..NUM_OF_COLORS).collect();
-    let mut rng = thread_rng();
-    colors.shuffle(&mut rng);
-    let mut map_pixels: Vec<bool> = vec!();
-    let mut pixel_count: u32 = 0;
-    for i in FOLDERS.iter(){
-        let path = Path::new(ROOT_FOLDER).join(Path::new(i));
-        fs::create_dir_all(path).unwrap();
+    let output = OutputRoot::new(&settings.root_folder);
+    if mode == OutputMode::Protect && !output.is_empty()?{
+        return Err(Error::OutputExists(output.root().to_path_buf()))
     }
-    height_map.save("mod/map_data/heightmap.png").unwrap();
-    moisture_map.save("mod/map_data/moisturemap.png").unwrap();
-    temperature_map.save("mod/map_data/temperaturemap.png").unwrap();
+    let generator = MapGenerator::new(settings);
+    let map = generator.run()?;
+    output.prepare(mode)?;
+    generator.export(&map, &output)?;
+    print_counts(&generator.settings, &map);
 
-    let terrain_map = Vec::<Terrain>::collect_terrain(&height_map, &moisture_map, &temperature_map);
-    terrain_map.to_image(width).save("mod/map_data/terrainmap.png").unwrap();
+    println!("{}", SystemTime::now().duration_since(start_time).unwrap().as_millis().to_string());
+    Ok(())
+}
 
-    for pixel in height_map.pixels(){
-        if pixel[0] > LAND_COLOR{
-            map_pixels.push(true);
-        }
-        else{
-            map_pixels.push(false);
-        }
-        pixel_count += 1;
-    }
-    let mut map: im::RgbImage = im::ImageBuffer::new(width, height);
-    for pixel in 0..pixel_count{
-        let coords = pixel.as_coords(width);
-        if map_pixels[pixel as usize]{
-            map.put_pixel(coords.x, coords.y, LAND_FLAT_COLOR);
-        }
-        else{
-            map.put_pixel(coords.x, coords.y, WATER_FLAT_COLOR);
-        }
+fn preview(settings: Settings, output: &str) -> Result<()>{
+    let generator = MapGenerator::new(settings);
+    let heightmap = generator.heightmap()?;
+    let climate = generator.climate(&heightmap);
+    let terrain = generator.terrain(&heightmap, &climate);
+    terrain.terrain_map.to_image(heightmap.width()).save(output).map_err(|error| Error::image(Path::new(output), error))?;
+    println!("Preview saved to {}", output);
+    Ok(())
+}
+
+fn validate(root_folder: &str) -> Result<()>{
+    let problems = validate::validate_mod(root_folder);
+    if problems.is_empty(){
+        println!("{} is valid", root_folder);
+        return Ok(())
     }
-    map.save("mod/gfx/map/terrain/flatmap.png").unwrap();
-    let mut grids = Vec::<Grid>::collect_grids(width, height, &map_pixels, &colors);
-    grids.pixels_to_provinces(width);
-    for x in 0..width{
-        for y in 0..height{
-            map.put_pixel(x, y, BLACK);
-        }
+    for problem in problems.iter(){
+        eprintln!("{}", problem);
     }
-    
-    grids.save_to_files(width, &mut map, &terrain_map);
+    process::exit(1);
+}
 
-    println!("{}", SystemTime::now().duration_since(start_time).unwrap().as_millis().to_string());
+fn print_counts(settings: &Settings, map: &GeneratedMap){
+    let target = |target: Option<u32>| target.map_or(String::new(), |target| format!(" (target {})", target));
+    println!("Provinces: {}", map.provinces.count());
+    println!("Land provinces: {}{}, grid size {}", map.provinces.count_kind(ProvinceKind::Land), target(settings.target_land_provinces), map.provinces.land_grid_size);
+    println!("Impassable provinces: {}", map.provinces.count_kind(ProvinceKind::Impassable));
+    println!("Sea zones: {}{}, grid size {}", map.provinces.count_kind(ProvinceKind::Sea), target(settings.target_sea_zones), map.provinces.sea_grid_size);
+}
+
+fn stats(settings: Settings) -> Result<()>{
+    let generator = MapGenerator::new(settings);
+    let map = generator.run()?;
+    let (heightmap, terrain_map) = (&map.heightmap, &map.terrain.terrain_map);
+    let land_count = heightmap.land_count();
+    println!("Seed: {}", generator.settings.seed);
+    println!("Size: {}x{}", heightmap.width(), heightmap.height());
+    println!("Land: {} pixels ({:.1}%)", land_count, land_count as f64 * 100.0 / heightmap.map_pixels.len() as f64);
+    print_counts(&generator.settings, &map);
+    println!("Lakes: {}", map.provinces.count_kind(ProvinceKind::Lake));
+    println!("River provinces: {}", map.provinces.count_kind(ProvinceKind::River));
+    println!("Sea crossings: {}", map.provinces.crossings.len());
+    println!("River pixels: {}", map.rivers.count());
+    println!("Continents: {}, islands: {}", map.titles.landmasses.count(), map.titles.landmasses.islands);
+    let tree = &map.titles.tree;
+    println!("Titles: {} empires, {} kingdoms, {} duchies, {} counties, {} baronies", tree.count(Rank::Empire), tree.count(Rank::Kingdom), tree.count(Rank::Duchy), tree.count(Rank::County), tree.count(Rank::Barony));
+    println!("Island links: {}, {} as straits", tree.island_links.len(), map.titles.island_crossings.len());
+    for terrain in Terrain::all().iter(){
+        let count = terrain_map.iter().filter(|&n| n == terrain).count();
+        println!("{}: {:.1}%", terrain, count as f64 * 100.0 / terrain_map.len() as f64);
+    }
+    Ok(())
 }
@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+const PLACE_NAMES: &str = include_str!("../data/place_names.txt");
+const START: char = '^';
+const END: char = '$';
+const MIN_LENGTH: usize = 4;
+const MAX_LENGTH: usize = 10;
+const ATTEMPTS: usize = 1000;
+const FALLBACK: &str = "haven";
+const MAX_CONSONANTS: usize = 2;
+const MAX_VOWELS: usize = 2;
+
+// second order markov chain over the letters of the bundled place names
+pub struct NameGenerator{
+    chain: HashMap<(char, char), Vec<char>>,
+    used: HashSet<String>,
+    rng: StdRng
+}
+impl NameGenerator{
+    pub fn new(rng: StdRng) -> Self{
+        let mut chain: HashMap<(char, char), Vec<char>> = HashMap::new();
+        for word in PLACE_NAMES.lines().map(str::trim).filter(|word| !word.is_empty()){
+            let letters: Vec<char> = [START, START].iter().copied().chain(word.chars()).chain([END].iter().copied()).collect();
+            for window in letters.windows(3){
+                chain.entry((window[0], window[1])).or_insert_with(Vec::new).push(window[2]);
+            }
+        }
+        NameGenerator{chain, used: HashSet::new(), rng}
+    }
+    fn word(&mut self) -> String{
+        let mut word = String::new();
+        let mut state = (START, START);
+        while word.len() <= MAX_LENGTH{
+            let rng = &mut self.rng;
+            let next = match self.chain.get(&state).and_then(|choices| choices.choose(rng)){
+                Some(&next) => next,
+                None => break
+            };
+            if next == END{
+                break
+            }
+            word.push(next);
+            state = (state.1, next);
+        }
+        word
+    }
+    // a capitalised name whose key has not been handed out before
+    pub fn name(&mut self) -> String{
+        let mut valid = None;
+        for _ in 0..ATTEMPTS{
+            let word = self.word();
+            if !usable(&word){
+                continue
+            }
+            let unique = !self.used.contains(&key(&word));
+            valid = Some(word);
+            if unique{
+                break
+            }
+        }
+        // the numeral suffix below only settles uniqueness, the word itself always passes the checks
+        let word = match valid{
+            Some(word) => word,
+            None => {
+                let places: Vec<&str> = PLACE_NAMES.lines().map(str::trim).filter(|place| usable(place)).collect();
+                String::from(*places.choose(&mut self.rng).unwrap_or(&FALLBACK))
+            }
+        };
+        let mut candidate = word.clone();
+        let mut suffix = 2;
+        while self.used.contains(&key(&candidate)){
+            candidate = format!("{} {}", word, roman(suffix));
+            suffix += 1;
+        }
+        self.used.insert(key(&candidate));
+        capitalise(&candidate)
+    }
+}
+
+// lowercase ascii form used after the rank prefix of a title key
+pub fn key(name: &str) -> String{
+    name.chars().map(|letter| if letter.is_ascii_alphanumeric() {letter.to_ascii_lowercase()} else {'_'}).collect()
+}
+
+fn usable(word: &str) -> bool{
+    word.len() >= MIN_LENGTH && word.len() <= MAX_LENGTH && pronounceable(word)
+}
+
+// rejects clusters the chain can splice together but nobody can say, like "zbr" or "aeio", an h after a consonant only softens it
+fn pronounceable(word: &str) -> bool{
+    let (mut consonants, mut vowels) = (0, 0);
+    for letter in word.chars(){
+        if "aeiouy".contains(letter){
+            vowels += 1;
+            consonants = 0;
+        }
+        else if letter != 'h' || consonants == 0{
+            consonants += 1;
+            vowels = 0;
+        }
+        if consonants > MAX_CONSONANTS || vowels > MAX_VOWELS{
+            return false
+        }
+    }
+    true
+}
+
+// english style demonym, Bamona gives Bamonan, Merg gives Mergian and Kessel II gives Kesselian II
+pub fn adjective(name: &str) -> String{
+    let (word, suffix) = match name.rsplit_once(' '){
+        Some((word, numeral)) if !numeral.is_empty() && numeral.chars().all(|letter| "IVX".contains(letter)) => (word, format!(" {}", numeral)),
+        _ => (name, String::new())
+    };
+    if word.ends_with('a'){
+        return format!("{}n{}", word, suffix)
+    }
+    match word.strip_suffix(|letter| "eiouy".contains(letter)){
+        Some(stem) => format!("{}ian{}", stem, suffix),
+        None => format!("{}ian{}", word, suffix)
+    }
+}
+
+fn capitalise(word: &str) -> String{
+    let mut letters = word.chars();
+    match letters.next(){
+        Some(first) => first.to_uppercase().chain(letters).collect(),
+        None => String::new()
+    }
+}
+
+fn roman(number: usize) -> String{
+    let numerals = [(10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I")];
+    let mut number = number;
+    let mut result = String::new();
+    for &(value, numeral) in numerals.iter(){
+        while number >= value{
+            result.push_str(numeral);
+            number -= value;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn adjectives_follow_the_last_letter(){
+        assert_eq!(adjective("Bamona"), "Bamonan");
+        assert_eq!(adjective("Merg"), "Mergian");
+        assert_eq!(adjective("Yare"), "Yarian");
+    }
+
+    #[test]
+    fn adjectives_keep_the_numeral_suffix(){
+        assert_eq!(adjective("Kessel II"), "Kesselian II");
+        assert_eq!(adjective("Tava IV"), "Tavan IV");
+    }
+
+    #[test]
+    fn numerals(){
+        assert_eq!(roman(2), "II");
+        assert_eq!(roman(4), "IV");
+        assert_eq!(roman(9), "IX");
+        assert_eq!(roman(14), "XIV");
+    }
+
+    #[test]
+    fn fallback_words_pass_the_checks(){
+        assert!(usable(FALLBACK));
+        assert!(PLACE_NAMES.lines().map(str::trim).any(usable));
+    }
+
+    #[test]
+    fn keys_are_lowercase_ascii(){
+        assert_eq!(key("Kessel II"), "kessel_ii");
+    }
+}
@@ -1,6 +1,8 @@
 use noise::{utils::PlaneMapBuilder, utils::NoiseMapBuilder, MultiFractal, Fbm};
+use crate::settings::NoiseSettings;
 
-pub fn generate_noise_map(width: u32, height: u32, octaves: usize, lacunarity: f64, persistance: f64, frequency: f64, scale: f64, sea_level: f64, divider: f64, seed: u32) -> im::ImageBuffer<im::Luma<u16>, Vec<u16>>{
+pub fn generate_noise_map(width: u32, height: u32, settings: &NoiseSettings, seed: u32) -> im::ImageBuffer<im::Luma<u16>, Vec<u16>>{
+    let NoiseSettings{octaves, lacunarity, persistance, frequency, scale, sea_level, divider, falloff} = *settings;
     let fbm = Fbm::new(seed)
         .set_frequency(frequency)
         .set_persistence(persistance)
@@ -18,6 +20,10 @@ pub fn generate_noise_map(width: u32, height: u32, octaves: usize, lacunarity: f
     for y in 0..height{
         for x in 0..width{
             let mut value = result.get_value(x as usize, y as usize);
+            if falloff != 0.0{
+                let (dx, dy) = (x as f64 / width as f64 * 2.0 - 1.0, y as f64 / height as f64 * 2.0 - 1.0);
+                value += falloff * (0.5 - ((dx * dx + dy * dy) / 2.0).sqrt());
+            }
             if value < sea_level{
                 value = sea_level;
             }
@@ -0,0 +1,85 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::FOLDERS;
+use crate::error::{Error, Result};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum OutputMode{
+    Protect,
+    Force,
+    Clean
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct OutputRoot{
+    root: PathBuf
+}
+impl OutputRoot{
+    pub fn new(root_folder: &str) -> Self{
+        OutputRoot{root: PathBuf::from(root_folder)}
+    }
+    pub fn root(&self) -> &Path{
+        &self.root
+    }
+    pub fn path(&self, relative: &str) -> PathBuf{
+        self.root.join(relative)
+    }
+    pub fn outer_descriptor_path(&self) -> PathBuf{
+        let name = self.root.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| String::from("mod"));
+        self.root.with_file_name(format!("{}.mod", name))
+    }
+    pub fn is_empty(&self) -> Result<bool>{
+        if !self.root.exists(){
+            return Ok(true)
+        }
+        let mut entries = fs::read_dir(&self.root).map_err(|error| Error::io(&self.root, error))?;
+        Ok(entries.next().is_none())
+    }
+    pub fn prepare(&self, mode: OutputMode) -> Result<()>{
+        match mode{
+            OutputMode::Protect => {
+                if !self.is_empty()?{
+                    return Err(Error::OutputExists(self.root.clone()))
+                }
+            },
+            OutputMode::Force => {},
+            OutputMode::Clean => {
+                for folder in FOLDERS.iter(){
+                    let path = self.path(folder);
+                    if path.exists(){
+                        fs::remove_dir_all(&path).map_err(|error| Error::io(&path, error))?;
+                    }
+                }
+            }
+        }
+        for folder in FOLDERS.iter(){
+            let path = self.path(folder);
+            fs::create_dir_all(&path).map_err(|error| Error::io(&path, error))?;
+        }
+        Ok(())
+    }
+    pub fn create_folder(&self, relative: &str) -> Result<()>{
+        let path = self.path(relative);
+        fs::create_dir_all(&path).map_err(|error| Error::io(&path, error))
+    }
+    pub fn write_file(&self, relative: &str, contents: &str) -> Result<()>{
+        write_file(&self.path(relative), contents)
+    }
+    pub fn write_outer_descriptor(&self, contents: &str) -> Result<()>{
+        write_file(&self.outer_descriptor_path(), contents)
+    }
+    pub fn absolute_root(&self) -> Result<PathBuf>{
+        fs::canonicalize(&self.root).map_err(|error| Error::io(&self.root, error))
+    }
+    pub fn save_image<F>(&self, relative: &str, save: F) -> Result<()>
+    where F: FnOnce(&Path) -> im::ImageResult<()>{
+        let path = self.path(relative);
+        save(&path).map_err(|error| Error::image(&path, error))
+    }
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()>{
+    let mut file = fs::File::create(path).map_err(|error| Error::io(path, error))?;
+    file.write_all(contents.as_bytes()).map_err(|error| Error::io(path, error))
+}
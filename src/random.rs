@@ -0,0 +1,16 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Stage{
+    Colors,
+    Grids,
+    SeaZones,
+    RiverProvinces,
+    Titles,
+    Names
+}
+
+pub fn stage_rng(seed: u32, stage: Stage) -> StdRng{
+    StdRng::seed_from_u64((seed as u64) << 32 | stage as u64)
+}
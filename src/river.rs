@@ -0,0 +1,207 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+use crate::{PINK, WHITE};
+use crate::error::{Error, Result};
+use crate::numastype::NumAsType;
+use crate::water::neighbour_indices;
+
+pub const SOURCE: u8 = 0;
+pub const MERGE: u8 = 1;
+pub const SPLIT: u8 = 2;
+pub const NARROWEST: u8 = 3;
+pub const WIDEST: u8 = 11;
+pub const WATER: u8 = 254;
+pub const LAND: u8 = 255;
+const PALETTE:[[u8; 3]; 12] = [
+    [0, 255, 0],
+    [255, 0, 0],
+    [255, 252, 0],
+    [0, 225, 255],
+    [0, 200, 255],
+    [0, 150, 255],
+    [0, 100, 255],
+    [0, 0, 255],
+    [0, 0, 225],
+    [0, 0, 200],
+    [0, 0, 150],
+    [0, 0, 100]
+];
+
+pub struct FlowMap{
+    pub downstream: Vec<Option<usize>>,
+    pub accumulation: Vec<f64>
+}
+
+pub fn compute_flow(height_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>, map_pixels: &Vec<bool>, moisture_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>) -> FlowMap{
+    let (width, height) = (height_map.width(), height_map.height());
+    let elevation: Vec<u64> = height_map.pixels().map(|pixel| (pixel[0] as u64) << 16).collect();
+    let mut downstream = vec![None; map_pixels.len()];
+    let mut filled = vec![0; map_pixels.len()];
+    let mut visited = vec![false; map_pixels.len()];
+    let mut queue = BinaryHeap::new();
+    for (index, is_land) in map_pixels.iter().enumerate(){
+        if !is_land{
+            visited[index] = true;
+            queue.push(Reverse((0, index)));
+        }
+    }
+    if queue.is_empty(){
+        for index in 0..map_pixels.len(){
+            if neighbour_indices(index, width, height).len() < 4{
+                visited[index] = true;
+                filled[index] = elevation[index];
+                queue.push(Reverse((elevation[index], index)));
+            }
+        }
+    }
+    let mut order = vec!();
+    while let Some(Reverse((level, index))) = queue.pop(){
+        if map_pixels[index]{
+            order.push(index);
+        }
+        for neighbour in neighbour_indices(index, width, height){
+            if visited[neighbour]{
+                continue
+            }
+            visited[neighbour] = true;
+            downstream[neighbour] = Some(index);
+            filled[neighbour] = elevation[neighbour].max(level + 1);
+            queue.push(Reverse((filled[neighbour], neighbour)));
+        }
+    }
+    let mut accumulation = vec![0.0; map_pixels.len()];
+    for &index in order.iter().rev(){
+        let coords = (index as u32).as_coords(width);
+        accumulation[index] += 0.5 + moisture_map.get_pixel(coords.x, coords.y)[0] as f64 / 65535.0;
+        if let Some(next) = downstream[index]{
+            if map_pixels[next]{
+                accumulation[next] += accumulation[index];
+            }
+        }
+    }
+    FlowMap{downstream, accumulation}
+}
+
+pub fn trace_rivers(flow: &FlowMap, map_pixels: &Vec<bool>, width: u32, height: u32, threshold: f64) -> Vec<u8>{
+    let mut rivers: Vec<u8> = map_pixels.iter().map(|&is_land| if is_land {LAND} else {WATER}).collect();
+    let mut upstream: Vec<Vec<usize>> = vec![vec!(); map_pixels.len()];
+    let mut mouths = vec!();
+    for (index, &next) in flow.downstream.iter().enumerate(){
+        if !map_pixels[index] || flow.accumulation[index] < threshold{
+            continue
+        }
+        match next{
+            Some(next) if map_pixels[next] => upstream[next].push(index),
+            Some(_) => mouths.push(index),
+            None => {}
+        }
+    }
+    let mut accepted = vec![false; map_pixels.len()];
+    let mut children: Vec<Vec<usize>> = vec![vec!(); map_pixels.len()];
+    let mut queue = BinaryHeap::new();
+    for &mouth in mouths.iter(){
+        queue.push((flow.accumulation[mouth] as u64, Reverse(mouth), None));
+    }
+    while let Some((_, Reverse(index), parent)) = queue.pop(){
+        let touches_other = neighbour_indices(index, width, height).iter()
+            .any(|&neighbour| accepted[neighbour] && Some(neighbour) != parent);
+        if touches_other{
+            continue
+        }
+        if let Some(parent) = parent{
+            if children[parent].len() >= 2{
+                continue
+            }
+            children[parent].push(index);
+        }
+        accepted[index] = true;
+        for &child in upstream[index].iter(){
+            queue.push((flow.accumulation[child] as u64, Reverse(child), Some(index)));
+        }
+    }
+    for index in 0..map_pixels.len(){
+        if !accepted[index]{
+            continue
+        }
+        let width_index = (flow.accumulation[index] / threshold).log2().floor() as u8;
+        rivers[index] = (NARROWEST + width_index).min(WIDEST);
+    }
+    for index in 0..map_pixels.len(){
+        if !accepted[index]{
+            continue
+        }
+        if children[index].is_empty(){
+            rivers[index] = SOURCE;
+        }
+    }
+    for index in 0..map_pixels.len(){
+        if accepted[index] && children[index].len() == 2{
+            let tributary = *children[index].iter().min_by(|a, b|
+                flow.accumulation[**a].partial_cmp(&flow.accumulation[**b]).unwrap().then(b.cmp(a))
+            ).unwrap();
+            rivers[tributary] = MERGE;
+        }
+    }
+    for index in 0..map_pixels.len(){
+        if rivers[index] != MERGE{
+            continue
+        }
+        if children[index].is_empty(){
+            remove_branch(index, &children, &mut rivers);
+        }
+    }
+    rivers
+}
+
+pub fn major_rivers(flow: &FlowMap, rivers: &Vec<u8>, width: u32, height: u32, threshold: f64, river_width: u32) -> Vec<bool>{
+    let mut major = vec![false; rivers.len()];
+    if river_width == 0{
+        return major
+    }
+    let radius = (river_width as i64 - 1) / 2;
+    for index in 0..rivers.len(){
+        if rivers[index] >= WATER || flow.accumulation[index] < threshold{
+            continue
+        }
+        let coords = (index as u32).as_coords(width);
+        for dy in -radius..=radius + (river_width as i64 - 1) % 2{
+            for dx in -radius..=radius + (river_width as i64 - 1) % 2{
+                let (x, y) = (coords.x as i64 + dx, coords.y as i64 + dy);
+                if x < 0 || y < 0 || x >= width as i64 || y >= height as i64{
+                    continue
+                }
+                let neighbour = (x + y * width as i64) as usize;
+                if rivers[neighbour] != WATER{
+                    major[neighbour] = true;
+                }
+            }
+        }
+    }
+    major
+}
+
+fn remove_branch(index: usize, children: &Vec<Vec<usize>>, rivers: &mut Vec<u8>){
+    rivers[index] = LAND;
+    for &child in children[index].iter(){
+        remove_branch(child, children, rivers);
+    }
+}
+
+pub fn save_rivers(rivers: &Vec<u8>, width: u32, height: u32, path: &Path) -> Result<()>{
+    let file = fs::File::create(path).map_err(|error| Error::io(path, error))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut palette = vec![0; 256 * 3];
+    for (i, color) in PALETTE.iter().enumerate(){
+        palette[i * 3..i * 3 + 3].copy_from_slice(color);
+    }
+    palette[WATER as usize * 3..WATER as usize * 3 + 3].copy_from_slice(&PINK.0);
+    palette[LAND as usize * 3..LAND as usize * 3 + 3].copy_from_slice(&WHITE.0);
+    encoder.set_palette(palette);
+    let mut writer = encoder.write_header().map_err(|error| Error::png(path, error))?;
+    writer.write_image_data(rivers).map_err(|error| Error::png(path, error))
+}
@@ -0,0 +1,166 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use crate::grid::{Coords, Grid, Layout};
+use crate::numastype::NumAsType;
+use crate::terrain::Terrain;
+
+pub const SEEDINGS:[&str; 3] = ["grid", "poisson", "lloyd"];
+// maximal poisson-disk sets hold about 0.7 points per radius squared, this matches the density of one seed per grid cell
+const RADIUS_FACTOR: f64 = 0.835;
+const MIN_SCALE: f64 = 0.5;
+const MAX_SCALE: f64 = 2.0;
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Seeding{
+    Grid,
+    Poisson,
+    Lloyd
+}
+impl Seeding{
+    pub fn from_name(name: &str) -> Option<Self>{
+        match name{
+            "grid" => Some(Seeding::Grid),
+            "poisson" => Some(Seeding::Poisson),
+            "lloyd" => Some(Seeding::Lloyd),
+            _ => None
+        }
+    }
+}
+
+// seeds bucketed into square cells so the nearest seed of a pixel is found by looking at nearby cells only
+struct SeedIndex{
+    cell_size: u32,
+    columns: u32,
+    rows: u32,
+    cells: Vec<Vec<usize>>
+}
+impl SeedIndex{
+    fn new(seeds: &[Coords], width: u32, height: u32, cell_size: u32) -> Self{
+        let (columns, rows) = ((width + cell_size - 1) / cell_size, (height + cell_size - 1) / cell_size);
+        let mut cells = vec![vec!(); (columns * rows) as usize];
+        for (i, seed) in seeds.iter().enumerate(){
+            cells[(seed.x / cell_size + seed.y / cell_size * columns) as usize].push(i);
+        }
+        SeedIndex{cell_size, columns, rows, cells}
+    }
+    fn ring(&self, coords: &Coords, ring: i64) -> Vec<usize>{
+        let (cx, cy) = ((coords.x / self.cell_size) as i64, (coords.y / self.cell_size) as i64);
+        let mut seeds = vec!();
+        for y in cy - ring..=cy + ring{
+            for x in cx - ring..=cx + ring{
+                if (x - cx).abs() != ring && (y - cy).abs() != ring{
+                    continue
+                }
+                if x < 0 || y < 0 || x >= self.columns as i64 || y >= self.rows as i64{
+                    continue
+                }
+                seeds.extend_from_slice(&self.cells[(x + y * self.columns as i64) as usize]);
+            }
+        }
+        seeds
+    }
+    // seeds ordered by distance, seeds outside the scanned rings are at least ring * cell_size away
+    fn nearest(&self, coords: &Coords, seeds: &[Coords]) -> Vec<usize>{
+        let mut found = vec!();
+        let max_ring = self.columns.max(self.rows) as i64;
+        for ring in 0..=max_ring{
+            found.extend(self.ring(coords, ring));
+            let reach = (ring * self.cell_size as i64) as u64;
+            if found.iter().any(|&seed| distance(coords, &seeds[seed]) <= reach * reach){
+                break
+            }
+        }
+        found.sort_by_key(|&seed| (distance(coords, &seeds[seed]), seed));
+        found
+    }
+}
+
+fn distance(a: &Coords, b: &Coords) -> u64{
+    let (dx, dy) = (a.x as i64 - b.x as i64, a.y as i64 - b.y as i64);
+    (dx * dx + dy * dy) as u64
+}
+
+pub fn radius(grid_size: u32) -> f64{
+    grid_size as f64 * RADIUS_FACTOR
+}
+
+// per pixel radius multipliers that make seed density follow habitability, normalised to the land average
+pub fn habitability_scales(terrain_map: &Vec<Terrain>, map_pixels: &Vec<bool>, strength: f64) -> Vec<f64>{
+    let land: Vec<f64> = terrain_map.iter().zip(map_pixels.iter()).filter(|(_, &is_land)| is_land).map(|(terrain, _)| terrain.habitability()).collect();
+    let mean = land.iter().sum::<f64>() / land.len().max(1) as f64;
+    terrain_map.iter().map(|terrain| (mean / terrain.habitability()).powf(strength / 2.0).max(MIN_SCALE).min(MAX_SCALE)).collect()
+}
+
+pub fn poisson_disk(map_pixels: &Vec<bool>, width: u32, height: u32, radius: f64, scales: Option<&Vec<f64>>, rng: &mut StdRng) -> Vec<Coords>{
+    let mut candidates: Vec<u32> = (0..map_pixels.len() as u32).filter(|&index| map_pixels[index as usize]).collect();
+    candidates.shuffle(rng);
+    let radius_at = |index: usize| radius * scales.map_or(1.0, |scales| scales[index]);
+    let cell_size = (radius.ceil() as u32).max(1);
+    let rings = if scales.is_some() {MAX_SCALE.ceil() as i64} else {1};
+    let mut index = SeedIndex::new(&[], width, height, cell_size);
+    let mut seeds: Vec<Coords> = vec!();
+    let mut radii: Vec<f64> = vec!();
+    for candidate in candidates{
+        let coords = candidate.as_coords(width);
+        let own = radius_at(candidate as usize);
+        let blocked = (0..=rings).flat_map(|ring| index.ring(&coords, ring))
+            .any(|seed| (distance(&coords, &seeds[seed]) as f64).sqrt() < (own + radii[seed]) / 2.0);
+        if blocked{
+            continue
+        }
+        index.cells[(coords.x / cell_size + coords.y / cell_size * index.columns) as usize].push(seeds.len());
+        seeds.push(coords);
+        radii.push(own);
+    }
+    seeds
+}
+
+fn assign(seeds: &[Coords], map_pixels: &Vec<bool>, width: u32, height: u32, cell_size: u32, barriers: Option<&Vec<bool>>) -> Vec<Vec<Coords>>{
+    let index = SeedIndex::new(seeds, width, height, cell_size);
+    let mut regions = vec![vec!(); seeds.len()];
+    for pixel in 0..map_pixels.len(){
+        if !map_pixels[pixel]{
+            continue
+        }
+        let coords = (pixel as u32).as_coords(width);
+        let nearest = index.nearest(&coords, seeds);
+        let seed = match barriers{
+            Some(barriers) => nearest.iter().copied().find(|&seed| coords.line_of_sight(&seeds[seed], width, barriers)).unwrap_or(nearest[0]),
+            None => nearest[0]
+        };
+        regions[seed].push(coords);
+    }
+    regions
+}
+
+// moves every seed to the pixel of its region closest to the region's centroid, weighted by the seed density of each pixel
+pub fn lloyd(seeds: Vec<Coords>, map_pixels: &Vec<bool>, width: u32, height: u32, cell_size: u32, scales: Option<&Vec<f64>>, iterations: u32) -> Vec<Coords>{
+    let weight = |coords: &Coords| scales.map_or(1.0, |scales| scales[coords.as_index(width) as usize].powi(-2));
+    let mut seeds = seeds;
+    for _ in 0..iterations{
+        let regions = assign(&seeds, map_pixels, width, height, cell_size, None);
+        seeds = regions.iter().zip(seeds.iter()).map(|(region, seed)|{
+            if region.is_empty(){
+                return *seed
+            }
+            let total: f64 = region.iter().map(|coords| weight(coords)).sum();
+            let centroid = Coords::new(
+                (region.iter().map(|coords| coords.x as f64 * weight(coords)).sum::<f64>() / total).round() as u32,
+                (region.iter().map(|coords| coords.y as f64 * weight(coords)).sum::<f64>() / total).round() as u32
+            );
+            *region.iter().min_by_key(|coords| distance(coords, &centroid)).unwrap()
+        }).collect();
+    }
+    seeds
+}
+
+pub fn seeded_grids(seeds: &[Coords], map_pixels: &Vec<bool>, layout: Layout, colors: &[u32], barriers: Option<&Vec<bool>>) -> Vec<Grid>{
+    let regions = assign(seeds, map_pixels, layout.width, layout.height, layout.grid_size, barriers);
+    regions.into_iter().enumerate().map(|(i, region)|{
+        let mut grid = Grid::new(vec!(seeds[i]), region.clone(), i as u32, colors, layout.kind);
+        grid.province_pixels = region;
+        grid
+    }).collect()
+}
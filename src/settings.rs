@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use crate::descriptor::DescriptorSettings;
+use crate::error::{Error, Result};
+use crate::growth::{Growth, GrowthCosts};
+use crate::localization::EXTRA_LANGUAGES;
+use crate::seeding::Seeding;
+use crate::terrain::Terrain;
+use crate::titles::TitleSettings;
+
+pub const PRESETS:[&str; 4] = ["earth-like", "pangaea", "archipelago", "inland-sea"];
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoiseSettings{
+    pub octaves: usize,
+    pub lacunarity: f64,
+    pub persistance: f64,
+    pub frequency: f64,
+    pub scale: f64,
+    pub sea_level: f64,
+    pub divider: f64,
+    pub falloff: f64
+}
+impl NoiseSettings{
+    pub fn height() -> Self{
+        NoiseSettings{octaves: 7, lacunarity: 2.12323, persistance: 0.5, frequency: 1.0, scale: 3.0, sea_level: -0.2, divider: 5.0, falloff: 0.0}
+    }
+    pub fn moisture() -> Self{
+        NoiseSettings{octaves: 3, lacunarity: 2.02345, persistance: 0.5, frequency: 0.5, scale: 3.0, sea_level: -0.9, divider: 3.5, falloff: 0.0}
+    }
+    pub fn jitter() -> Self{
+        NoiseSettings{octaves: 4, lacunarity: 2.0, persistance: 0.5, frequency: 1.0, scale: 24.0, sea_level: -1.0, divider: 4.0, falloff: 0.0}
+    }
+    pub fn temperature() -> Self{
+        NoiseSettings{octaves: 3, lacunarity: 2.201348, persistance: 0.5, frequency: 0.5, scale: 3.0, sea_level: -0.9, divider: 3.5, falloff: 0.0}
+    }
+    fn validate(&self, name: &str) -> Result<()>{
+        if self.octaves == 0 || self.octaves > 32{
+            return Err(Error::Config(format!("{} octaves must be between 1 and 32", name)))
+        }
+        if self.lacunarity <= 0.0 || self.persistance <= 0.0 || self.frequency <= 0.0 || self.scale <= 0.0{
+            return Err(Error::Config(format!("{} lacunarity, persistance, frequency and scale must be positive", name)))
+        }
+        if self.sea_level < -1.0 || self.sea_level >= 1.0{
+            return Err(Error::Config(format!("{} sea_level must be between -1 and 1", name)))
+        }
+        if self.divider <= 0.0{
+            return Err(Error::Config(format!("{} divider must be positive", name)))
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TerrainTuning{
+    pub terrain: Terrain,
+    pub biases: [f64; 3],
+    pub weights: [f64; 3]
+}
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings{
+    pub seed: u32,
+    pub width: u32,
+    pub height: u32,
+    pub province_grid_size: u32,
+    pub sea_zone_grid_size: u32,
+    pub seeding: Seeding,
+    pub lloyd_iterations: u32,
+    pub terrain_sizing: f64,
+    pub growth: Growth,
+    pub growth_costs: GrowthCosts,
+    pub target_land_provinces: Option<u32>,
+    pub target_sea_zones: Option<u32>,
+    pub min_province_size: u32,
+    pub border_jitter: f64,
+    pub impassable_mountain_share: f64,
+    pub min_lake_size: u32,
+    pub max_lake_size: u32,
+    pub river_threshold: f64,
+    pub river_province_threshold: f64,
+    pub river_province_width: u32,
+    pub max_crossing_distance: u32,
+    pub land_color: u16,
+    pub root_folder: String,
+    pub extra_languages: Vec<String>,
+    pub titles: TitleSettings,
+    pub descriptor: DescriptorSettings,
+    pub height_noise: NoiseSettings,
+    pub moisture_noise: NoiseSettings,
+    pub temperature_noise: NoiseSettings,
+    pub jitter_noise: NoiseSettings,
+    pub terrain: Vec<TerrainTuning>
+}
+impl Default for Settings{
+    fn default() -> Self{
+        Settings{
+            seed: 9787,
+            width: 1024,
+            height: 512,
+            province_grid_size: 64,
+            sea_zone_grid_size: 128,
+            seeding: Seeding::Grid,
+            lloyd_iterations: 3,
+            terrain_sizing: 0.0,
+            growth: Growth::Distance,
+            growth_costs: GrowthCosts::default(),
+            target_land_provinces: None,
+            target_sea_zones: None,
+            min_province_size: 100,
+            border_jitter: 0.0,
+            impassable_mountain_share: 0.75,
+            min_lake_size: 16,
+            max_lake_size: 4000,
+            river_threshold: 400.0,
+            river_province_threshold: 12000.0,
+            river_province_width: 3,
+            max_crossing_distance: 12,
+            land_color: 4352,
+            root_folder: String::from("mod"),
+            extra_languages: vec!(),
+            titles: TitleSettings::default(),
+            descriptor: DescriptorSettings::default(),
+            height_noise: NoiseSettings::height(),
+            moisture_noise: NoiseSettings::moisture(),
+            temperature_noise: NoiseSettings::temperature(),
+            jitter_noise: NoiseSettings::jitter(),
+            terrain: vec!()
+        }
+    }
+}
+impl Settings{
+    pub fn preset(name: &str) -> Option<Self>{
+        let mut settings = Settings::default();
+        match name{
+            "earth-like" => {},
+            "pangaea" => {
+                settings.height_noise.scale = 1.5;
+                settings.height_noise.octaves = 6;
+                settings.height_noise.falloff = 1.5;
+                settings.land_color = 1500;
+            },
+            "archipelago" => {
+                settings.height_noise.scale = 7.0;
+                settings.height_noise.persistance = 0.55;
+                settings.land_color = 9000;
+            },
+            "inland-sea" => {
+                settings.height_noise.scale = 2.0;
+                settings.height_noise.sea_level = -0.6;
+                settings.height_noise.falloff = -1.5;
+                settings.land_color = 3000;
+            },
+            _ => return None
+        }
+        Some(settings)
+    }
+    pub fn load(path: &str, base: Option<&str>) -> Result<Self>{
+        let text = fs::read_to_string(path).map_err(|error| Error::io(Path::new(path), error))?;
+        let mut file: Value = match Path::new(path).extension().and_then(|extension| extension.to_str()){
+            Some("json") => serde_json::from_str(&text).map_err(|error| Error::Config(format!("{}: {}", path, error)))?,
+            Some("toml") => toml::from_str(&text).map_err(|error| Error::Config(format!("{}: {}", path, error)))?,
+            _ => return Err(Error::Config(format!("{}: profiles must be .toml or .json files", path)))
+        };
+        let file_preset = match file.as_object_mut().and_then(|object| object.remove("preset")){
+            Some(Value::String(preset)) => Some(preset),
+            Some(_) => return Err(Error::Config(format!("{}: preset must be a string", path))),
+            None => None
+        };
+        // a preset given on the command line overrides the one named in the profile
+        let preset = base.map(String::from).or(file_preset);
+        let base = match preset{
+            Some(preset) => Settings::preset(&preset).ok_or_else(|| unknown_preset(&preset))?,
+            None => Settings::default()
+        };
+        let mut merged = serde_json::to_value(base).map_err(|error| Error::Config(error.to_string()))?;
+        merge(&mut merged, file);
+        serde_json::from_value(merged).map_err(|error| Error::Config(format!("{}: {}", path, error)))
+    }
+    // english is always written, the extra languages get the same strings
+    pub fn languages(&self) -> Vec<&str>{
+        std::iter::once("english").chain(self.extra_languages.iter().map(String::as_str)).collect()
+    }
+    pub fn validate(&self) -> Result<()>{
+        if self.width == 0 || self.height == 0{
+            return Err(Error::Config(String::from("width and height must be positive")))
+        }
+        if self.province_grid_size == 0 || self.province_grid_size > self.width || self.province_grid_size > self.height{
+            return Err(Error::Config(String::from("province_grid_size must be positive and fit inside the map")))
+        }
+        if self.sea_zone_grid_size == 0 || self.sea_zone_grid_size > self.width || self.sea_zone_grid_size > self.height{
+            return Err(Error::Config(String::from("sea_zone_grid_size must be positive and fit inside the map")))
+        }
+        if self.target_land_provinces == Some(0) || self.target_sea_zones == Some(0){
+            return Err(Error::Config(String::from("target_land_provinces and target_sea_zones must be positive")))
+        }
+        if self.terrain_sizing < 0.0 || self.terrain_sizing > 2.0{
+            return Err(Error::Config(String::from("terrain_sizing must be between 0 and 2")))
+        }
+        if self.growth_costs.elevation < 0.0 || self.growth_costs.river < 0.0 || self.growth_costs.terrain < 0.0{
+            return Err(Error::Config(String::from("growth_costs must not be negative")))
+        }
+        if self.border_jitter < 0.0{
+            return Err(Error::Config(String::from("border_jitter must not be negative")))
+        }
+        if self.impassable_mountain_share < 0.0 || self.impassable_mountain_share > 1.0{
+            return Err(Error::Config(String::from("impassable_mountain_share must be between 0 and 1")))
+        }
+        if self.min_lake_size > self.max_lake_size{
+            return Err(Error::Config(String::from("min_lake_size must not be larger than max_lake_size")))
+        }
+        if self.river_threshold <= 0.0{
+            return Err(Error::Config(String::from("river_threshold must be positive")))
+        }
+        if self.river_province_threshold < self.river_threshold{
+            return Err(Error::Config(String::from("river_province_threshold must not be smaller than river_threshold")))
+        }
+        for (rank, range) in self.titles.ranges().iter(){
+            if range.min == 0 || range.min > range.max{
+                return Err(Error::Config(format!("{} sizes must be at least 1 with min not above max", rank)))
+            }
+        }
+        if self.titles.fertility_skew < 0.0{
+            return Err(Error::Config(String::from("fertility_skew must not be negative")))
+        }
+        for language in self.extra_languages.iter(){
+            if !EXTRA_LANGUAGES.contains(&language.as_str()){
+                return Err(Error::Config(format!("extra_languages must be supported languages other than english, not {}", language)))
+            }
+        }
+        if self.root_folder.is_empty(){
+            return Err(Error::Config(String::from("root_folder must not be empty")))
+        }
+        if self.descriptor.name.is_empty() || self.descriptor.name.contains('"'){
+            return Err(Error::Config(String::from("descriptor name must not be empty or contain quotes")))
+        }
+        self.height_noise.validate("height_noise")?;
+        self.moisture_noise.validate("moisture_noise")?;
+        self.temperature_noise.validate("temperature_noise")?;
+        self.jitter_noise.validate("jitter_noise")?;
+        for tuning in self.terrain.iter(){
+            if tuning.weights.iter().any(|&weight| weight < 0.0){
+                return Err(Error::Config(format!("terrain weights for {} must not be negative", tuning.terrain)))
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn unknown_preset(name: &str) -> Error{
+    Error::Config(format!("unknown preset {}, expected one of: {}", name, PRESETS.join(", ")))
+}
+
+fn merge(base: &mut Value, file: Value){
+    match (base, file){
+        (Value::Object(base), Value::Object(file)) => {
+            for (key, value) in file{
+                match base.get_mut(&key){
+                    Some(existing) => merge(existing, value),
+                    None => {base.insert(key, value);}
+                }
+            }
+        },
+        (base, file) => *base = file
+    }
+}
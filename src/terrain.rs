@@ -1,10 +1,14 @@
+use crate::error::Result;
 use crate::numastype::NumAsType;
-use crate::{LAND_COLOR};
+use crate::settings::{Settings, TerrainTuning};
 use itertools::izip;
+use serde::{Deserialize, Serialize};
+use crate::output::OutputRoot;
 
 const LUMA_WHITE: im::Luma<u8> = im::Luma([255]);
 
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Terrain {
     Mountains,
     DesertMountain,
@@ -61,6 +65,26 @@ impl Terrain{
             Terrain::Ocean => "beach_02_mask.png"
         }
     }
+    // how densely the game packs provinces into this terrain, 1.0 being the most fertile land
+    pub fn habitability(&self) -> f64{
+        match self {
+            Terrain::Mountains => 0.3,
+            Terrain::DesertMountain => 0.2,
+            Terrain::Hills => 0.6,
+            Terrain::Jungle => 0.45,
+            Terrain::Drylands => 0.6,
+            Terrain::Desert => 0.25,
+            Terrain::Oasis => 0.8,
+            Terrain::Floodplains => 1.0,
+            Terrain::Plains => 0.9,
+            Terrain::Farmlands => 1.0,
+            Terrain::Forest => 0.65,
+            Terrain::Wetlands => 0.5,
+            Terrain::Steppe => 0.45,
+            Terrain::Taiga => 0.3,
+            Terrain::Ocean => 1.0
+        }
+    }
     fn biases(&self) -> [f64; 3]{
         match self {
             Terrain::Mountains => [0.9, 0.1, -0.1],
@@ -99,9 +123,13 @@ impl Terrain{
             Terrain::Ocean => [5.0, 5.0, 5.0]
         }
     }
-    pub fn calculate_likeliness(&self, values: [f64; 3]) -> f64{
+    pub fn calculate_likeliness(&self, values: [f64; 3], tuning: &[TerrainTuning]) -> f64{
+        let (biases, weights) = match tuning.iter().find(|tuning| tuning.terrain == *self){
+            Some(tuning) => (tuning.biases, tuning.weights),
+            None => (self.biases(), self.weights())
+        };
         let mut likeliness = 0.0;
-        for (value, bias, weight) in izip!(values, self.biases(), self.weights()){
+        for (value, bias, weight) in izip!(values, biases, weights){
             likeliness += (value-bias).powf(2.0)*weight
         }
         likeliness
@@ -180,21 +208,24 @@ impl MapValue{
 pub trait TerrainVector{
     fn collect_terrain(height_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
         moisture_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
-        temperature_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>) -> Self;
+        temperature_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
+        settings: &Settings) -> (Self, Vec<im::GrayImage>) where Self: Sized;
     fn calculate_map_values(height_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
         moisture_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
-        temperature_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>) -> (MapValue, MapValue, MapValue);
-    fn sorted_terrains_colors(self, num: usize, normalized_values: [f64; 3]) -> (Vec<Terrain>, Vec<im::Luma<u8>>);
+        temperature_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
+        land_color: u16) -> (MapValue, MapValue, MapValue);
+    fn sorted_terrains_colors(self, num: usize, normalized_values: [f64; 3], tuning: &[TerrainTuning]) -> (Vec<Terrain>, Vec<im::Luma<u8>>);
     fn to_image(&self, width: u32) -> im::RgbImage;
-    fn generate_gfx(&self, width: u32);
+    fn generate_gfx(&self, width: u32, output: &OutputRoot) -> Result<()>;
 }
 impl TerrainVector for Vec::<Terrain>{
     fn collect_terrain(height_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
         moisture_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
-        temperature_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>) -> Self{
+        temperature_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
+        settings: &Settings) -> (Self, Vec<im::GrayImage>){
         let (width, height) = (height_map.width(), height_map.height());
         let mut map = vec!();
-        let (elevation_value, moisture_value, temperature_value) = Vec::<Terrain>::calculate_map_values(height_map, moisture_map, temperature_map);
+        let (elevation_value, moisture_value, temperature_value) = Vec::<Terrain>::calculate_map_values(height_map, moisture_map, temperature_map, settings.land_color);
         let mut images = vec!();
         for _terrain in Terrain::all().iter(){
             let image: im::ImageBuffer<im::Luma<u8>, Vec<u8>> = im::ImageBuffer::new(width, height);
@@ -204,35 +235,33 @@ impl TerrainVector for Vec::<Terrain>{
             for x in 0..width{
                 let (elevation, moisture, temperature) =
                     (height_map.get_pixel(x, y)[0], moisture_map.get_pixel(x, y)[0], temperature_map.get_pixel(x, y)[0]);
-                if elevation <= LAND_COLOR{
+                if elevation <= settings.land_color{
                     let terrain = Terrain::Ocean;
                     map.push(terrain);
                     images[terrain.as_index()].put_pixel(x, y, LUMA_WHITE);
                     continue
                 }
                 let normalized_values = [elevation_value.normalize_value(elevation), moisture_value.normalize_value(moisture), temperature_value.normalize_value(temperature)];
-                let (terrains, colors) = Terrain::all().sorted_terrains_colors(3, normalized_values);
+                let (terrains, colors) = Terrain::all().sorted_terrains_colors(3, normalized_values, &settings.terrain);
                 for (terrain, color) in izip!(terrains.iter(), colors){
                     images[terrain.as_index()].put_pixel(x, y, color);
                 }
                 map.push(terrains[0]);
             }
         }
-        for (terrain, image) in Terrain::all().iter().zip(images.iter()){
-            image.save("mod/gfx/map/terrain/".to_owned() + terrain.file()).unwrap();
-        }
-        map
+        (map, images)
     }
     fn calculate_map_values(height_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
         moisture_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
-        temperature_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>) -> (MapValue, MapValue, MapValue){
+        temperature_map: &im::ImageBuffer<im::Luma<u16>, Vec<u16>>,
+        land_color: u16) -> (MapValue, MapValue, MapValue){
         let (width, height) = (height_map.width(), height_map.height());
         let (mut elevation_value, mut moisture_value, mut temperature_value) = (MapValue::new(), MapValue::new(), MapValue::new());
         for y in 0..height{
             for x in 0..width{
                 let (elevation, moisture, temperature) =
                     (height_map.get_pixel(x, y)[0], moisture_map.get_pixel(x, y)[0], temperature_map.get_pixel(x, y)[0]);
-                if elevation <= LAND_COLOR{
+                if elevation <= land_color{
                     continue
                 }
                 elevation_value.apply_value(elevation);
@@ -245,16 +274,16 @@ impl TerrainVector for Vec::<Terrain>{
         temperature_value.calculate_average();
         (elevation_value, moisture_value, temperature_value)
     }
-    fn sorted_terrains_colors(mut self, num: usize, normalized_values: [f64; 3]) -> (Vec<Terrain>, Vec<im::Luma<u8>>){
+    fn sorted_terrains_colors(mut self, num: usize, normalized_values: [f64; 3], tuning: &[TerrainTuning]) -> (Vec<Terrain>, Vec<im::Luma<u8>>){
         self.sort_by(
             |a, b|
-            a.calculate_likeliness(normalized_values)
-            .partial_cmp(&b.calculate_likeliness(normalized_values)).unwrap()
+            a.calculate_likeliness(normalized_values, tuning)
+            .partial_cmp(&b.calculate_likeliness(normalized_values, tuning)).unwrap()
         );
         let (mut terrains, mut values, mut colors) = (vec!(), vec!(), vec!());
         for i in 0..num{
             terrains.push(self[i]);
-            values.push(self[i].calculate_likeliness(normalized_values));
+            values.push(self[i].calculate_likeliness(normalized_values, tuning));
         }
         let total_value: f64 = values.iter().sum();
         let powered_total = total_value;
@@ -271,7 +300,7 @@ impl TerrainVector for Vec::<Terrain>{
         }
         image
     }
-    fn generate_gfx(&self, width: u32){
+    fn generate_gfx(&self, width: u32, output: &OutputRoot) -> Result<()>{
         let height = self.len() as u32 / width;
         let terrains = Terrain::all();
         let mut images = vec!();
@@ -284,8 +313,13 @@ impl TerrainVector for Vec::<Terrain>{
             let coords = (i as u32).as_coords(width);
             images[index].put_pixel(coords.x, coords.y, LUMA_WHITE);
         }
-        for (terrain, image) in terrains.iter().zip(images.iter()){
-            image.save("mod/gfx/map/terrain/".to_owned() + terrain.file()).unwrap();
-        }
+        save_masks(&images, output)
+    }
+}
+
+pub fn save_masks(masks: &[im::GrayImage], output: &OutputRoot) -> Result<()>{
+    for (terrain, image) in Terrain::all().iter().zip(masks.iter()){
+        output.save_image(&format!("gfx/map/terrain/{}", terrain.file()), |path| image.save(path))?;
     }
+    Ok(())
 }
\ No newline at end of file
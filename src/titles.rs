@@ -0,0 +1,458 @@
+use std::cmp::Reverse;
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::adjacencies::Crossing;
+use crate::graph::{EdgeKind, ProvinceGraph};
+use crate::grid::{Grid, GridVector, ProvinceKind};
+use crate::names::{self, NameGenerator};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Rank{
+    Barony,
+    County,
+    Duchy,
+    Kingdom,
+    Empire
+}
+impl Rank{
+    pub fn prefix(&self) -> &'static str{
+        match self{
+            Rank::Barony => "b",
+            Rank::County => "c",
+            Rank::Duchy => "d",
+            Rank::Kingdom => "k",
+            Rank::Empire => "e"
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SizeRange{
+    pub min: u32,
+    pub max: u32
+}
+impl SizeRange{
+    pub fn new(min: u32, max: u32) -> Self{
+        SizeRange{min, max}
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountyCapital{
+    Central,
+    Developed
+}
+
+// how many titles of the rank below each title groups together
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TitleSettings{
+    pub county: SizeRange,
+    pub duchy: SizeRange,
+    pub kingdom: SizeRange,
+    pub empire: SizeRange,
+    pub fertility_skew: f64,
+    pub county_capital: CountyCapital,
+    pub min_continent_size: u32,
+    pub titular_titles: u32
+}
+impl Default for TitleSettings{
+    fn default() -> Self{
+        TitleSettings{
+            county: SizeRange::new(1, 7),
+            duchy: SizeRange::new(3, 6),
+            kingdom: SizeRange::new(3, 7),
+            empire: SizeRange::new(2, 5),
+            fertility_skew: 1.5,
+            county_capital: CountyCapital::Developed,
+            min_continent_size: 8000,
+            titular_titles: 0
+        }
+    }
+}
+impl TitleSettings{
+    pub fn ranges(&self) -> [(&'static str, SizeRange); 4]{
+        [("county", self.county), ("duchy", self.duchy), ("kingdom", self.kingdom), ("empire", self.empire)]
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Title{
+    pub rank: Rank,
+    pub key: String,
+    pub name: String,
+    pub color: im::Rgb<u8>,
+    pub capital: u32,
+    pub province: Option<u32>,
+    pub children: Vec<usize>
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+struct Province{
+    id: u32,
+    centroid: (f64, f64),
+    color: im::Rgb<u8>,
+    fertility: f64,
+    continent: usize
+}
+
+// a set of provinces that is clustered as one piece at the next rank
+#[derive(PartialEq, Clone, Debug)]
+struct Unit{
+    title: usize,
+    provinces: Vec<usize>,
+    centroid: (f64, f64)
+}
+
+pub struct TitleTree{
+    pub titles: Vec<Title>,
+    pub roots: Vec<usize>,
+    // province id pairs tied together only to keep titles connected, they are not real adjacencies
+    pub island_links: Vec<(u32, u32)>
+}
+impl TitleTree{
+    // fertility holds a value between 0 and 1 and continents the continent if any for every province id, starting at province 1
+    pub fn build(grids: &Vec<Grid>, graph: &ProvinceGraph, crossings: &[Crossing], fertility: &[f64], continents: &[Option<usize>], settings: &TitleSettings, rng: &mut StdRng) -> Self{
+        let mut provinces: Vec<Province> = grids.provinces().iter().enumerate().filter(|(_, grid)| grid.kind == ProvinceKind::Land).map(|(i, grid)|{
+            let count = grid.province_pixels.len() as f64;
+            Province{
+                id: i as u32 + 1,
+                centroid: (
+                    grid.province_pixels.iter().map(|coords| coords.x as f64).sum::<f64>() / count,
+                    grid.province_pixels.iter().map(|coords| coords.y as f64).sum::<f64>() / count
+                ),
+                color: grid.color,
+                fertility: fertility[i],
+                continent: continents[i].unwrap_or(usize::MAX)
+            }
+        }).collect();
+        // islands too small to be continents join the continent of the closest province
+        for i in 0..provinces.len(){
+            if provinces[i].continent != usize::MAX{
+                continue
+            }
+            let closest = provinces.iter().filter(|other| other.continent != usize::MAX)
+                .min_by(|a, b| distance(a.centroid, provinces[i].centroid).partial_cmp(&distance(b.centroid, provinces[i].centroid)).unwrap());
+            provinces[i].continent = closest.map_or(0, |closest| closest.continent);
+        }
+        let (links, island_links) = province_links(&provinces, graph, crossings);
+        let mut titles = vec!();
+        let mut units = vec!();
+        for (i, province) in provinces.iter().enumerate(){
+            units.push(Unit{title: titles.len(), provinces: vec!(i), centroid: province.centroid});
+            titles.push(Title{
+                rank: Rank::Barony, key: format!("b_{}", province.id), name: String::new(), color: province.color,
+                capital: province.id, province: Some(province.id), children: vec!()
+            });
+        }
+        let ranks = [(Rank::County, settings.county), (Rank::Duchy, settings.duchy), (Rank::Kingdom, settings.kingdom), (Rank::Empire, settings.empire)];
+        for (rank, range) in ranks.iter(){
+            let mut neighbours = unit_links(&units, &links, provinces.len());
+            if *rank != Rank::Empire{
+                let continent = |unit: usize| provinces[units[unit].provinces[0]].continent;
+                for (unit, others) in neighbours.iter_mut().enumerate(){
+                    others.retain(|&other| continent(other) == continent(unit));
+                }
+            }
+            let groups = if *rank == Rank::County{
+                let skew = settings.fertility_skew;
+                cluster(&units, &neighbours, *range, rng, |unit, rng|
+                    skewed_size(*range, provinces[units[unit].provinces[0]].fertility, skew, rng)
+                )
+            }
+            else{
+                cluster(&units, &neighbours, *range, rng, |_, rng| rng.gen_range(range.min..=range.max) as usize)
+            };
+            let mut next = vec!();
+            for (n, group) in groups.iter().enumerate(){
+                let members: Vec<usize> = group.iter().flat_map(|&unit| units[unit].provinces.clone()).collect();
+                let centroid = mean(members.iter().map(|&province| provinces[province].centroid));
+                let capital_unit = *group.iter().min_by(|a, b|
+                    distance(units[**a].centroid, centroid).partial_cmp(&distance(units[**b].centroid, centroid)).unwrap()
+                ).unwrap();
+                let capital = if *rank == Rank::County{
+                    county_capital(&members, &provinces, settings.county_capital)
+                }
+                else{
+                    titles[units[capital_unit].title].capital
+                };
+                let color = titles[units[capital_unit].title].color;
+                let mut children: Vec<usize> = group.iter().map(|&unit| units[unit].title).collect();
+                // the game seats a county in its first barony
+                if let Some(position) = children.iter().position(|&child| titles[child].province == Some(capital)){
+                    let barony = children.remove(position);
+                    children.insert(0, barony);
+                }
+                next.push(Unit{title: titles.len(), provinces: members, centroid});
+                titles.push(Title{
+                    rank: *rank, key: format!("{}_{}", rank.prefix(), n + 1), name: String::new(), color,
+                    capital, province: None, children
+                });
+            }
+            units = next;
+        }
+        let mut roots: Vec<usize> = units.iter().map(|unit| unit.title).collect();
+        roots.extend(titular_titles(&mut titles, &roots, settings.titular_titles));
+        TitleTree{titles, roots, island_links}
+    }
+    // names every title and derives its key from the name, keys stay unique because the generator never repeats one
+    pub fn assign_names(&mut self, generator: &mut NameGenerator){
+        for title in self.titles.iter_mut(){
+            title.name = generator.name();
+            title.key = format!("{}_{}", title.rank.prefix(), names::key(&title.name));
+        }
+    }
+    pub fn count(&self, rank: Rank) -> usize{
+        self.titles.iter().filter(|title| title.rank == rank).count()
+    }
+    pub fn county_of(&self, province: u32) -> Option<&Title>{
+        self.titles.iter().find(|title| title.rank == Rank::County && title.children.iter().any(|&child| self.titles[child].province == Some(province)))
+    }
+    pub fn landed_titles(&self) -> String{
+        let mut landed_titles = String::new();
+        for &root in self.roots.iter(){
+            self.write_title(root, 0, &mut landed_titles);
+        }
+        landed_titles
+    }
+    fn write_title(&self, index: usize, depth: usize, output: &mut String){
+        let title = &self.titles[index];
+        let indent = "\t".repeat(depth);
+        let color = title.color;
+        output.push_str(&format!("{}{} = {{\n", indent, title.key));
+        output.push_str(&format!("{}\tcolor = {{ {} {} {} }}\n", indent, color[0], color[1], color[2]));
+        output.push_str(&format!("{}\tcolor2 = {{ 255 255 255 }}\n", indent));
+        if let Some(province) = title.province{
+            output.push_str(&format!("{}\tprovince = {}\n", indent, province));
+        }
+        if !matches!(title.rank, Rank::Barony | Rank::County){
+            if let Some(county) = self.county_of(title.capital){
+                output.push_str(&format!("{}\tcapital = {}\n", indent, county.key));
+            }
+        }
+        for &child in title.children.iter(){
+            self.write_title(child, depth + 1, output);
+        }
+        output.push_str(&format!("{}}}\n", indent));
+    }
+}
+
+// titular kingdoms without de jure land, seated in the largest duchies of each empire that do not hold a kingdom capital
+fn titular_titles(titles: &mut Vec<Title>, empires: &[usize], per_empire: u32) -> Vec<usize>{
+    let mut result = vec!();
+    for &empire in empires.iter(){
+        let kingdoms = titles[empire].children.clone();
+        let capitals: Vec<u32> = kingdoms.iter().map(|&kingdom| titles[kingdom].capital).collect();
+        let mut duchies: Vec<usize> = kingdoms.iter().flat_map(|&kingdom| titles[kingdom].children.clone())
+            .filter(|&duchy| !capitals.contains(&titles[duchy].capital)).collect();
+        duchies.sort_by_key(|&duchy| (Reverse(titles[duchy].children.len()), duchy));
+        for &duchy in duchies.iter().take(per_empire as usize){
+            let (capital, color) = (titles[duchy].capital, titles[duchy].color);
+            result.push(titles.len());
+            titles.push(Title{
+                rank: Rank::Kingdom, key: format!("k_titular_{}", result.len()), name: String::new(), color,
+                capital, province: None, children: vec!()
+            });
+        }
+    }
+    result
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64{
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn mean<I: Iterator<Item = (f64, f64)>>(points: I) -> (f64, f64){
+    let (mut x, mut y, mut count) = (0.0, 0.0, 0.0);
+    for point in points{
+        x += point.0;
+        y += point.1;
+        count += 1.0;
+    }
+    (x / count, y / count)
+}
+
+// the most fertile or the most central barony, the other measure breaks ties
+fn county_capital(members: &[usize], provinces: &[Province], choice: CountyCapital) -> u32{
+    let total = |a: usize| members.iter().map(|&b| distance(provinces[a].centroid, provinces[b].centroid)).sum::<f64>();
+    let central = |a: &usize, b: &usize| total(*a).partial_cmp(&total(*b)).unwrap();
+    let developed = |a: &usize, b: &usize| provinces[*b].fertility.partial_cmp(&provinces[*a].fertility).unwrap();
+    let capital = match choice{
+        CountyCapital::Central => members.iter().min_by(|a, b| central(a, b).then(developed(a, b))),
+        CountyCapital::Developed => members.iter().min_by(|a, b| developed(a, b).then(central(a, b)))
+    };
+    provinces[*capital.unwrap()].id
+}
+
+// fertile land draws sizes from the top of the range and barren land from the bottom, a skew of 0 draws evenly
+fn skewed_size(range: SizeRange, fertility: f64, skew: f64, rng: &mut StdRng) -> usize{
+    let exponent = 2f64.powf(skew * (1.0 - 2.0 * fertility));
+    let share = rng.gen::<f64>().powf(exponent);
+    (range.min + (share * (range.max - range.min + 1) as f64) as u32).min(range.max) as usize
+}
+
+// land borders and sea crossings between titled provinces, pieces of a continent are then tied to their nearest neighbour
+// on the same continent and finally continents to each other
+fn province_links(provinces: &[Province], graph: &ProvinceGraph, crossings: &[Crossing]) -> (Vec<Vec<usize>>, Vec<(u32, u32)>){
+    let mut position = vec![None; graph.nodes.len() + 1];
+    for (i, province) in provinces.iter().enumerate(){
+        position[province.id as usize] = Some(i);
+    }
+    let mut links = vec![vec!(); provinces.len()];
+    let pairs = graph.edges.iter().filter(|edge| edge.kind == EdgeKind::Land).map(|edge| (edge.from, edge.to))
+        .chain(crossings.iter().map(|crossing| (crossing.from, crossing.to)));
+    for (from, to) in pairs{
+        if let (Some(a), Some(b)) = (position[from as usize], position[to as usize]){
+            if !links[a].contains(&b){
+                links[a].push(b);
+                links[b].push(a);
+            }
+        }
+    }
+    let within = link_components(provinces, &links, |a, b| provinces[a].continent == provinces[b].continent);
+    for &(a, b) in within.iter(){
+        links[a].push(b);
+        links[b].push(a);
+    }
+    let across = link_components(provinces, &links, |_, _| true);
+    for &(a, b) in across.iter(){
+        links[a].push(b);
+        links[b].push(a);
+    }
+    let island_links = within.iter().chain(across.iter()).map(|&(a, b)| (provinces[a].id.min(provinces[b].id), provinces[a].id.max(provinces[b].id))).collect();
+    (links, island_links)
+}
+
+fn components(links: &[Vec<usize>]) -> Vec<usize>{
+    let mut component = vec![usize::MAX; links.len()];
+    let mut count = 0;
+    for start in 0..links.len(){
+        if component[start] != usize::MAX{
+            continue
+        }
+        let mut stack = vec!(start);
+        component[start] = count;
+        while let Some(current) = stack.pop(){
+            for &next in links[current].iter(){
+                if component[next] == usize::MAX{
+                    component[next] = count;
+                    stack.push(next);
+                }
+            }
+        }
+        count += 1;
+    }
+    component
+}
+
+// prim's algorithm over components, every step adds the closest allowed pair of provinces between a connected and a new component
+fn link_components<F: Fn(usize, usize) -> bool>(provinces: &[Province], links: &[Vec<usize>], allowed: F) -> Vec<(usize, usize)>{
+    let component = components(links);
+    let mut connected = vec![false; provinces.len()];
+    let mut best: Vec<Option<(f64, usize)>> = vec![None; provinces.len()];
+    let mut result = vec!();
+    let connect = |start: usize, connected: &mut Vec<bool>, best: &mut Vec<Option<(f64, usize)>>|{
+        let members: Vec<usize> = (0..provinces.len()).filter(|&i| component[i] == component[start]).collect();
+        for &member in members.iter(){
+            connected[member] = true;
+        }
+        for &member in members.iter(){
+            for other in 0..provinces.len(){
+                if connected[other] || !allowed(member, other){
+                    continue
+                }
+                let d = distance(provinces[member].centroid, provinces[other].centroid);
+                if best[other].map_or(true, |(current, _)| d < current){
+                    best[other] = Some((d, member));
+                }
+            }
+        }
+    };
+    for start in 0..provinces.len(){
+        if connected[start]{
+            continue
+        }
+        connect(start, &mut connected, &mut best);
+        loop{
+            let next = (0..provinces.len()).filter(|&i| !connected[i] && best[i].is_some())
+                .min_by(|a, b| best[*a].unwrap().0.partial_cmp(&best[*b].unwrap().0).unwrap().then(a.cmp(b)));
+            let next = match next{
+                Some(next) => next,
+                None => break
+            };
+            let (_, from) = best[next].unwrap();
+            result.push((from.min(next), from.max(next)));
+            connect(next, &mut connected, &mut best);
+        }
+    }
+    result
+}
+
+fn unit_links(units: &[Unit], links: &[Vec<usize>], province_count: usize) -> Vec<Vec<usize>>{
+    let mut owner = vec![0; province_count];
+    for (i, unit) in units.iter().enumerate(){
+        for &province in unit.provinces.iter(){
+            owner[province] = i;
+        }
+    }
+    let mut result = vec![vec!(); units.len()];
+    for (i, unit) in units.iter().enumerate(){
+        for &province in unit.provinces.iter(){
+            for &other in links[province].iter(){
+                if owner[other] != i && !result[i].contains(&owner[other]){
+                    result[i].push(owner[other]);
+                }
+            }
+        }
+    }
+    result
+}
+
+// grows groups of adjacent units from the most remote unassigned unit, then folds undersized groups into a neighbour
+fn cluster<F: Fn(usize, &mut StdRng) -> usize>(units: &[Unit], links: &[Vec<usize>], range: SizeRange, rng: &mut StdRng, target: F) -> Vec<Vec<usize>>{
+    let mut group_of: Vec<Option<usize>> = vec![None; units.len()];
+    let mut groups: Vec<Vec<usize>> = vec!();
+    loop{
+        let free = |unit: usize, group_of: &Vec<Option<usize>>| links[unit].iter().filter(|&&other| group_of[other].is_none()).count();
+        let start = match (0..units.len()).filter(|&unit| group_of[unit].is_none()).min_by_key(|&unit| (free(unit, &group_of), unit)){
+            Some(start) => start,
+            None => break
+        };
+        let target = target(start, rng);
+        let mut group = vec!(start);
+        group_of[start] = Some(groups.len());
+        while group.len() < target{
+            let centroid = mean(group.iter().map(|&unit| units[unit].centroid));
+            let next = group.iter().flat_map(|&unit| links[unit].iter().copied())
+                .filter(|&other| group_of[other].is_none())
+                .min_by(|a, b| distance(units[*a].centroid, centroid).partial_cmp(&distance(units[*b].centroid, centroid)).unwrap().then(a.cmp(b)));
+            match next{
+                Some(next) => {
+                    group_of[next] = Some(groups.len());
+                    group.push(next);
+                },
+                None => break
+            }
+        }
+        groups.push(group);
+    }
+    for i in 0..groups.len(){
+        if groups[i].is_empty() || groups[i].len() >= range.min as usize{
+            continue
+        }
+        let neighbour = groups[i].iter().flat_map(|&unit| links[unit].iter().map(|&other| group_of[other].unwrap()))
+            .filter(|&other| other != i && groups[other].len() + groups[i].len() <= range.max as usize)
+            .min_by_key(|&other| (groups[other].len(), other));
+        if let Some(neighbour) = neighbour{
+            let moved = std::mem::take(&mut groups[i]);
+            for &unit in moved.iter(){
+                group_of[unit] = Some(neighbour);
+            }
+            groups[neighbour].extend(moved);
+        }
+    }
+    groups.into_iter().filter(|group| !group.is_empty()).collect()
+}
@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+use std::fs;
+use crate::localization;
+use crate::output::OutputRoot;
+
+const REQUIRED_FILES:[&str; 10] = [
+    "descriptor.mod",
+    "map_data/default.map",
+    "map_data/provinces.png",
+    "map_data/heightmap.png",
+    "map_data/definition.csv",
+    "map_data/adjacencies.csv",
+    "common/landed_titles/00_landed_titles.txt",
+    "common/province_terrain/00_province_terrain.txt",
+    "localization/english/random_map_titles_l_english.yml",
+    "localization/english/random_map_provinces_l_english.yml"
+];
+
+pub fn validate_mod(root_folder: &str) -> Vec<String>{
+    let root = OutputRoot::new(root_folder);
+    let mut problems = vec!();
+    for file in REQUIRED_FILES.iter(){
+        if !root.path(file).is_file(){
+            problems.push(format!("missing {}", root.path(file).display()));
+        }
+    }
+    if !problems.is_empty(){
+        return problems
+    }
+    for file in [localization::TITLES_FILE, localization::PROVINCES_FILE].iter(){
+        let path = localization::path("english", file);
+        match fs::read_to_string(root.path(&path)){
+            Ok(contents) if !localization::has_header(&contents, "english") => problems.push(format!("{} does not start with a byte order mark and l_english:", path)),
+            Ok(_) => {},
+            Err(error) => problems.push(format!("cannot read {}: {}", path, error))
+        }
+    }
+    let provinces = match im::open(root.path("map_data/provinces.png")){
+        Ok(image) => image.into_rgb8(),
+        Err(error) => return vec!(format!("cannot read provinces.png: {}", error))
+    };
+    match im::image_dimensions(root.path("map_data/heightmap.png")){
+        Ok(dimensions) if dimensions != provinces.dimensions() => problems.push(format!(
+            "heightmap.png is {}x{} but provinces.png is {}x{}",
+            dimensions.0, dimensions.1, provinces.width(), provinces.height()
+        )),
+        Ok(_) => {},
+        Err(error) => problems.push(format!("cannot read heightmap.png: {}", error))
+    }
+    let definition = match fs::read_to_string(root.path("map_data/definition.csv")){
+        Ok(definition) => definition,
+        Err(error) => return vec!(format!("cannot read definition.csv: {}", error))
+    };
+    let mut defined_colors = HashSet::new();
+    for (line_number, line) in definition.lines().enumerate().skip(1){
+        let fields: Vec<&str> = line.split(';').collect();
+        if fields.len() < 4{
+            problems.push(format!("definition.csv line {} has too few fields", line_number + 1));
+            continue
+        }
+        if fields[0] != line_number.to_string(){
+            problems.push(format!("definition.csv line {} has id {} instead of {}", line_number + 1, fields[0], line_number));
+        }
+        let color: Vec<u8> = fields[1..4].iter().filter_map(|field| field.parse().ok()).collect();
+        if color.len() != 3{
+            problems.push(format!("definition.csv line {} has an invalid color", line_number + 1));
+            continue
+        }
+        if !defined_colors.insert([color[0], color[1], color[2]]){
+            problems.push(format!("definition.csv line {} repeats the color {:?}", line_number + 1, color));
+        }
+    }
+    let unassigned = provinces.pixels().filter(|pixel| pixel.0 == [0, 0, 0]).count();
+    if unassigned > 0{
+        problems.push(format!("provinces.png has {} pixels without a province", unassigned));
+    }
+    let used_colors: HashSet<[u8; 3]> = provinces.pixels().map(|pixel| pixel.0).collect();
+    for color in used_colors.difference(&defined_colors){
+        if *color != [0, 0, 0]{
+            problems.push(format!("provinces.png uses the undefined color {:?}", color));
+        }
+    }
+    for color in defined_colors.difference(&used_colors){
+        problems.push(format!("definition.csv defines the unused color {:?}", color));
+    }
+    problems
+}
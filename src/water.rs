@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+use crate::grid::Coords;
+use crate::numastype::NumAsType;
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct WaterBody{
+    pub pixels: Vec<Coords>,
+    pub touches_edge: bool
+}
+impl WaterBody{
+    pub fn is_lake(&self, max_lake_size: u32) -> bool{
+        !self.touches_edge && self.pixels.len() <= max_lake_size as usize
+    }
+}
+
+pub fn neighbour_indices(index: usize, width: u32, height: u32) -> Vec<usize>{
+    let coords = (index as u32).as_coords(width);
+    let mut neighbours = vec!();
+    if coords.x > 0{
+        neighbours.push(index - 1);
+    }
+    if coords.x + 1 < width{
+        neighbours.push(index + 1);
+    }
+    if coords.y > 0{
+        neighbours.push(index - width as usize);
+    }
+    if coords.y + 1 < height{
+        neighbours.push(index + width as usize);
+    }
+    neighbours
+}
+
+pub fn collect_water_bodies(map_pixels: &Vec<bool>, width: u32, height: u32) -> Vec<WaterBody>{
+    let mut visited = vec![false; map_pixels.len()];
+    let mut bodies = vec!();
+    for start in 0..map_pixels.len(){
+        if map_pixels[start] || visited[start]{
+            continue
+        }
+        let mut body = WaterBody{pixels: vec!(), touches_edge: false};
+        let mut queue = VecDeque::new();
+        visited[start] = true;
+        queue.push_back(start);
+        while let Some(index) = queue.pop_front(){
+            let coords = (index as u32).as_coords(width);
+            if coords.x == 0 || coords.y == 0 || coords.x + 1 == width || coords.y + 1 == height{
+                body.touches_edge = true;
+            }
+            body.pixels.push(coords);
+            for neighbour in neighbour_indices(index, width, height){
+                if !map_pixels[neighbour] && !visited[neighbour]{
+                    visited[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        bodies.push(body);
+    }
+    bodies
+}